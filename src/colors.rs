@@ -0,0 +1,215 @@
+use crate::saveread::Empire;
+use std::{collections::HashMap, error::Error, fs};
+
+///map colors of the named colors empires pick for their flags, as 0xRRGGBB.
+const DEFAULTS: [(&str, u32); 26] = [
+    ("dark_brown", 0x4D3319),
+    ("brown", 0x80552B),
+    ("beige", 0xC2AD85),
+    ("yellow", 0xE6C619),
+    ("light_orange", 0xF2A23D),
+    ("orange", 0xE67317),
+    ("red_orange", 0xD9461A),
+    ("red", 0xC21F1F),
+    ("burgundy", 0x801A33),
+    ("pink", 0xE673B3),
+    ("purple", 0x9933B3),
+    ("dark_purple", 0x59267A),
+    ("indigo", 0x3D2E99),
+    ("dark_blue", 0x1F338C),
+    ("blue", 0x2E6BD9),
+    ("light_blue", 0x6BB3F2),
+    ("turquoise", 0x2EC2B8),
+    ("dark_teal", 0x195C59),
+    ("teal", 0x269985),
+    ("light_green", 0x8CD95C),
+    ("green", 0x339933),
+    ("dark_green", 0x1F5926),
+    ("grey", 0x999999),
+    ("dark_grey", 0x4D4D4D),
+    ("black", 0x1A1A1A),
+    ("white", 0xF2F2F2),
+];
+
+///lookup from the color names used in saves to map colors, as 0xRRGGBB.
+#[derive(Debug, Clone)]
+pub struct ColorTable {
+    colors: HashMap<String, u32>,
+}
+
+impl ColorTable {
+    ///returns a table with the built-in colors.
+    pub fn new() -> ColorTable {
+        ColorTable {
+            colors: DEFAULTS
+                .iter()
+                .map(|(a, b)| ((*a).to_owned(), *b))
+                .collect(),
+        }
+    }
+    ///returns the built-in colors, overridden by the map colors in the file at path,
+    /// see ColorTable::parse.
+    pub fn load(path: &str) -> Result<ColorTable, Box<dyn Error>> {
+        ColorTable::parse(&fs::read_to_string(path)?)
+    }
+    ///returns the built-in colors, overridden by the map colors in the text of a copy of the game's
+    /// flags/colors.txt. Colors in the file can be given as hsv { h s v }, with values from 0 to 1,
+    /// hsv360 { h s v } with h in degrees and s and v from 0 to 100, or rgb { r g b }.
+    pub fn parse(inp: &str) -> Result<ColorTable, Box<dyn Error>> {
+        let mut ret = ColorTable::new();
+        let file: String = inp
+            .lines()
+            .map(|x| x.split('#').next().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .replace('{', " { ")
+            .replace('}', " } ")
+            .replace('=', " = ");
+        let mut it = file.split_whitespace().peekable();
+        let mut depth = 0;
+        let mut name = "";
+        let mut key = "";
+        while let Some(token) = it.next() {
+            match token {
+                "{" => {
+                    depth += 1;
+                    if depth == 2 {
+                        name = key.trim_matches('"');
+                    }
+                }
+                "}" => depth -= 1,
+                "=" => {}
+                "map" if depth == 2 && it.peek() == Some(&"=") => {
+                    it.next();
+                    let model = match it.next() {
+                        Some("{") => "rgb",
+                        Some(a) => {
+                            it.next();
+                            a
+                        }
+                        None => return Err(format!("Unexpected end of file for >{}<", name).into()),
+                    };
+                    let mut values = Vec::new();
+                    for value in it.by_ref() {
+                        if value == "}" {
+                            break;
+                        }
+                        values.push(value.parse::<f64>()?);
+                    }
+                    if values.len() < 3 {
+                        return Err(format!("Too few values in the map color of >{}<", name).into());
+                    }
+                    let color = match model {
+                        "hsv" => hsv(values[0], values[1], values[2]),
+                        "hsv360" => hsv(values[0] / 360.0, values[1] / 100.0, values[2] / 100.0),
+                        "rgb" => rgb(values[0], values[1], values[2]),
+                        a => {
+                            return Err(format!("Unknown color model >{}< for >{}<", a, name).into())
+                        }
+                    };
+                    ret.colors.insert(name.to_owned(), color);
+                }
+                _ => key = token,
+            }
+        }
+        Ok(ret)
+    }
+    ///returns the table from input/colors.txt, where a copy of the game's flags/colors.txt can be
+    /// put next to the saves, or the built-in colors if there is none.
+    pub fn find() -> Result<ColorTable, Box<dyn Error>> {
+        if std::path::Path::new("input/colors.txt").exists() {
+            ColorTable::load("input/colors.txt")
+        } else {
+            Ok(ColorTable::new())
+        }
+    }
+    ///returns the map color for a color name as used in saves, or None if the name is unknown.
+    pub fn rgb(&self, name: &str) -> Option<u32> {
+        self.colors.get(name).copied()
+    }
+    ///returns the map color of an empire, or None if it has none.
+    pub fn empire(&self, empire: &Empire) -> Option<u32> {
+        self.rgb(empire.map_color()?)
+    }
+}
+
+impl Default for ColorTable {
+    fn default() -> ColorTable {
+        ColorTable::new()
+    }
+}
+
+///packs r, g and b from 0 to 255 into 0xRRGGBB.
+fn rgb(r: f64, g: f64, b: f64) -> u32 {
+    let channel = |x: f64| x.round().clamp(0.0, 255.0) as u32;
+    (channel(r) << 16) + (channel(g) << 8) + channel(b)
+}
+
+///turns h, s and v from 0 to 1 into 0xRRGGBB.
+fn hsv(h: f64, s: f64, v: f64) -> u32 {
+    let h = (h.fract() + 1.0).fract() * 6.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as usize {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    rgb((r + m) * 255.0, (g + m) * 255.0, (b + m) * 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_every_color_model() {
+        let table = ColorTable::parse(
+            "colors = {
+	# a comment = { map = hsv { 0 0 0 } }
+	red = {
+		flag = hsv { 0.0 0.8 0.8 }
+		map = hsv { 0 1 1 }
+		ship = hsv { 0.0 0.8 0.8 }
+	}
+	\"green\" = {
+		map = hsv360 { 120 100 50 }
+	}
+	mauve = {
+		map = { 200 100 150 }
+	}
+	teal = {
+		map = rgb { 0 128 128 }
+	}
+}",
+        )
+        .unwrap();
+        assert_eq!(table.rgb("red"), Some(0xFF0000));
+        assert_eq!(table.rgb("green"), Some(0x008000));
+        assert_eq!(table.rgb("mauve"), Some(0xC86496));
+        assert_eq!(table.rgb("teal"), Some(0x008080));
+        // colors the file leaves out keep their built-in map color
+        assert_eq!(table.rgb("black"), Some(0x1A1A1A));
+        assert_eq!(table.rgb("a"), None);
+    }
+
+    #[test]
+    fn parse_rejects_broken_colors() {
+        assert!(ColorTable::parse("colors = { red = { map = hsv { 0 1 } } }").is_err());
+        assert!(ColorTable::parse("colors = { red = { map = cmyk { 0 1 1 0 } } }").is_err());
+        assert!(ColorTable::parse("colors = { red = { map = rgb { 0 a 1 } } }").is_err());
+    }
+
+    #[test]
+    fn hsv_goes_round_the_hues() {
+        assert_eq!(hsv(0.0, 1.0, 1.0), 0xFF0000);
+        assert_eq!(hsv(1.0 / 3.0, 1.0, 1.0), 0x00FF00);
+        assert_eq!(hsv(2.0 / 3.0, 1.0, 1.0), 0x0000FF);
+        assert_eq!(hsv(1.0, 1.0, 1.0), 0xFF0000);
+        assert_eq!(hsv(0.5, 0.0, 0.5), 0x808080);
+    }
+}
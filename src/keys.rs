@@ -0,0 +1,423 @@
+use minifb::Key;
+use std::{collections::HashMap, error::Error, fs};
+
+///what the viewer can be told to do with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    PanUp,
+    PanLeft,
+    PanDown,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    Home,
+    PreviousDate,
+    NextDate,
+    Play,
+    Slower,
+    Faster,
+    Record,
+    Screenshot,
+    NextMode,
+    PreviousMode,
+    Labels,
+    Heatmap,
+    Territory,
+    Hyperlanes,
+    Tooltips,
+    Search,
+    Help,
+}
+
+impl Action {
+    ///every action, in the order the help lists them.
+    pub const ALL: [Action; 24] = [
+        Action::Quit,
+        Action::PanUp,
+        Action::PanLeft,
+        Action::PanDown,
+        Action::PanRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Home,
+        Action::PreviousDate,
+        Action::NextDate,
+        Action::Play,
+        Action::Slower,
+        Action::Faster,
+        Action::Record,
+        Action::Screenshot,
+        Action::NextMode,
+        Action::PreviousMode,
+        Action::Labels,
+        Action::Heatmap,
+        Action::Territory,
+        Action::Hyperlanes,
+        Action::Tooltips,
+        Action::Search,
+        Action::Help,
+    ];
+    ///returns how the action is called in the bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::PanUp => "pan_up",
+            Action::PanLeft => "pan_left",
+            Action::PanDown => "pan_down",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::Home => "home",
+            Action::PreviousDate => "previous_date",
+            Action::NextDate => "next_date",
+            Action::Play => "play",
+            Action::Slower => "slower",
+            Action::Faster => "faster",
+            Action::Record => "record",
+            Action::Screenshot => "screenshot",
+            Action::NextMode => "next_mode",
+            Action::PreviousMode => "previous_mode",
+            Action::Labels => "labels",
+            Action::Heatmap => "heatmap",
+            Action::Territory => "territory",
+            Action::Hyperlanes => "hyperlanes",
+            Action::Tooltips => "tooltips",
+            Action::Search => "search",
+            Action::Help => "help",
+        }
+    }
+    ///whether the action happens again and again while its key is held, as moving the map and
+    /// stepping through the saves do. Everything else happens once for every press.
+    pub fn repeats(self) -> bool {
+        matches!(
+            self,
+            Action::PanUp
+                | Action::PanLeft
+                | Action::PanDown
+                | Action::PanRight
+                | Action::ZoomIn
+                | Action::ZoomOut
+                | Action::PreviousDate
+                | Action::NextDate
+        )
+    }
+    ///returns what the action does, for the help.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::PanUp => "pan up",
+            Action::PanLeft => "pan left",
+            Action::PanDown => "pan down",
+            Action::PanRight => "pan right",
+            Action::ZoomIn => "zoom in",
+            Action::ZoomOut => "zoom out",
+            Action::Home => "show the whole galaxy",
+            Action::PreviousDate => "previous save",
+            Action::NextDate => "next save",
+            Action::Play => "play or pause",
+            Action::Slower => "play slower",
+            Action::Faster => "play faster",
+            Action::Record => "start or stop recording frames",
+            Action::Screenshot => "save a screenshot",
+            Action::NextMode => "next map mode",
+            Action::PreviousMode => "previous map mode",
+            Action::Labels => "change which systems are named",
+            Action::Heatmap => "show or hide the heatmap",
+            Action::Territory => "show or hide the territory",
+            Action::Hyperlanes => "show or hide the hyperlanes",
+            Action::Tooltips => "turn tooltips on or off",
+            Action::Search => "search",
+            Action::Help => "show or hide this help",
+        }
+    }
+}
+
+///the keys of every action unless the bindings file says otherwise.
+const DEFAULTS: [(Action, &[Key]); 24] = [
+    (Action::Quit, &[Key::Escape]),
+    (Action::PanUp, &[Key::W]),
+    (Action::PanLeft, &[Key::A]),
+    (Action::PanDown, &[Key::S]),
+    (Action::PanRight, &[Key::D]),
+    (Action::ZoomIn, &[Key::Equal, Key::NumPadPlus]),
+    (Action::ZoomOut, &[Key::Minus, Key::NumPadMinus]),
+    (Action::Home, &[Key::Home]),
+    (Action::PreviousDate, &[Key::Left]),
+    (Action::NextDate, &[Key::Right]),
+    (Action::Play, &[Key::Space]),
+    (Action::Slower, &[Key::Comma]),
+    (Action::Faster, &[Key::Period]),
+    (Action::Record, &[Key::R]),
+    (Action::Screenshot, &[Key::P]),
+    (Action::NextMode, &[Key::M]),
+    (Action::PreviousMode, &[Key::N]),
+    (Action::Labels, &[Key::L]),
+    (Action::Heatmap, &[Key::H]),
+    (Action::Territory, &[Key::Key1]),
+    (Action::Hyperlanes, &[Key::Key2]),
+    (Action::Tooltips, &[Key::T]),
+    (Action::Search, &[Key::Slash, Key::F]),
+    (Action::Help, &[Key::F1]),
+];
+
+///the keys that can be bound, by the names they have in the bindings file.
+const KEYS: [Key; 94] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Apostrophe,
+    Key::Backquote,
+    Key::Backslash,
+    Key::Comma,
+    Key::Equal,
+    Key::LeftBracket,
+    Key::Minus,
+    Key::Period,
+    Key::RightBracket,
+    Key::Semicolon,
+    Key::Slash,
+    Key::Backspace,
+    Key::Delete,
+    Key::End,
+    Key::Enter,
+    Key::Escape,
+    Key::Home,
+    Key::Insert,
+    Key::PageDown,
+    Key::PageUp,
+    Key::Pause,
+    Key::Space,
+    Key::Tab,
+    Key::NumPad0,
+    Key::NumPad1,
+    Key::NumPad2,
+    Key::NumPad3,
+    Key::NumPad4,
+    Key::NumPad5,
+    Key::NumPad6,
+    Key::NumPad7,
+    Key::NumPad8,
+    Key::NumPad9,
+    Key::NumPadDot,
+    Key::NumPadSlash,
+    Key::NumPadAsterisk,
+    Key::NumPadMinus,
+    Key::NumPadPlus,
+    Key::NumPadEnter,
+    Key::Menu,
+    Key::CapsLock,
+    Key::ScrollLock,
+];
+
+///which keys do what. Every key does at most one thing, and an action can have any
+/// number of keys.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Bindings {
+    ///returns the built-in bindings.
+    pub fn new() -> Bindings {
+        Bindings {
+            keys: DEFAULTS.iter().map(|(a, b)| (*a, b.to_vec())).collect(),
+        }
+    }
+    ///returns the built-in bindings, with the actions in the file at path bound to the keys
+    /// given for them instead, see Bindings::parse.
+    pub fn load(path: &str) -> Result<Bindings, Box<dyn Error>> {
+        Bindings::parse(&fs::read_to_string(path)?)
+    }
+    ///returns the built-in bindings, with the actions in the text of a bindings file bound to the
+    /// keys given for them instead. Every line binds an action to the keys after it, as in
+    /// zoom_in = Equal NumPadPlus, with the keys named as in minifb, and anything after a #
+    /// is left out.
+    /// A key taken from another action leaves it, and an action with no keys can't be done.
+    pub fn parse(inp: &str) -> Result<Bindings, Box<dyn Error>> {
+        let mut ret = Bindings::new();
+        for line in inp.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (name, keys) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Expected action = keys, got >{}<", line))?;
+            let action = Action::ALL
+                .iter()
+                .find(|x| x.name() == name.trim())
+                .ok_or_else(|| format!("Unknown action >{}<", name.trim()))?;
+            let keys = keys
+                .split_whitespace()
+                .map(|x| key(x).ok_or_else(|| format!("Unknown key >{}< for >{}<", x, name.trim())))
+                .collect::<Result<Vec<Key>, String>>()?;
+            for other in ret.keys.values_mut() {
+                other.retain(|x| !keys.contains(x));
+            }
+            ret.keys.insert(*action, keys);
+        }
+        Ok(ret)
+    }
+    ///returns the bindings from input/keys.txt, next to the saves, or the built-in ones
+    /// if there is none.
+    pub fn find() -> Result<Bindings, Box<dyn Error>> {
+        if std::path::Path::new("input/keys.txt").exists() {
+            Bindings::load("input/keys.txt")
+        } else {
+            Ok(Bindings::new())
+        }
+    }
+    ///returns what the key does, if anything.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|x| self.keys.get(x).is_some_and(|y| y.contains(&key)))
+    }
+    ///returns a line for every action with keys, naming them and what the action does,
+    /// leaving out those that can't be done now.
+    pub fn help(&self, without: &[Action]) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .filter(|x| !without.contains(x))
+            .filter_map(|x| {
+                let keys = self.keys.get(x).filter(|y| !y.is_empty())?;
+                let keys: Vec<String> = keys.iter().map(|y| format!("{:?}", y)).collect();
+                Some(format!("{}: {}", keys.join(", "), x.describe()))
+            })
+            .collect()
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::new()
+    }
+}
+
+///returns the key with the name, ignoring case.
+fn key(name: &str) -> Option<Key> {
+    KEYS.iter()
+        .copied()
+        .find(|x| format!("{:?}", x).eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_binds_the_keys_given() {
+        let bindings = Bindings::parse(
+            "# arrows instead of wasd
+pan_up = Up
+ pan_down=down   # names ignore case
+zoom_in = Equal PageUp
+
+",
+        )
+        .unwrap();
+        assert_eq!(bindings.action(Key::Up), Some(Action::PanUp));
+        assert_eq!(bindings.action(Key::Down), Some(Action::PanDown));
+        assert_eq!(bindings.action(Key::PageUp), Some(Action::ZoomIn));
+        assert_eq!(bindings.action(Key::Equal), Some(Action::ZoomIn));
+        // keys of the actions in the file are replaced, those of the rest stay
+        assert_eq!(bindings.action(Key::W), None);
+        assert_eq!(bindings.action(Key::NumPadPlus), None);
+        assert_eq!(bindings.action(Key::A), Some(Action::PanLeft));
+    }
+
+    #[test]
+    fn parse_takes_keys_from_other_actions() {
+        let bindings = Bindings::parse("heatmap = Key1 T\nhelp =").unwrap();
+        assert_eq!(bindings.action(Key::Key1), Some(Action::Heatmap));
+        assert_eq!(bindings.action(Key::T), Some(Action::Heatmap));
+        assert_eq!(bindings.action(Key::H), None);
+        assert_eq!(bindings.action(Key::F1), None);
+        // actions left without keys are left out of the help
+        let help = bindings.help(&[]);
+        assert!(help.contains(&String::from("Key1, T: show or hide the heatmap")));
+        assert!(!help
+            .iter()
+            .any(|x| x.ends_with("show or hide the territory")));
+        assert!(!help.iter().any(|x| x.ends_with("show or hide this help")));
+        assert_eq!(help.len(), 21);
+        assert_eq!(bindings.help(&[Action::Search]).len(), 20);
+    }
+
+    #[test]
+    fn only_moving_and_stepping_repeat() {
+        let repeating: Vec<Action> = Action::ALL
+            .iter()
+            .copied()
+            .filter(|x| x.repeats())
+            .collect();
+        assert_eq!(repeating.len(), 8);
+        assert!(repeating.contains(&Action::NextDate));
+        for a in [
+            Action::Screenshot,
+            Action::Record,
+            Action::Play,
+            Action::Help,
+        ] {
+            assert!(!a.repeats());
+        }
+    }
+
+    #[test]
+    fn parse_rejects_unknown_names() {
+        assert!(Bindings::parse("zoom_in Equal").is_err());
+        assert!(Bindings::parse("zoom = Equal").is_err());
+        assert!(Bindings::parse("zoom_in = Plus").is_err());
+    }
+}
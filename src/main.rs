@@ -1,30 +1,80 @@
-use bmp;
-use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
-use std::{collections::BTreeMap, fmt::Debug, time::Duration};
+use flate2::{write::ZlibEncoder, Compression};
+use keys::Action;
+use minifb::{KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    fs,
+    io::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
+mod colors;
+mod keys;
+mod map;
+mod render;
 mod saveread;
+mod search;
+mod svg;
+mod text;
+mod timeline;
+mod ui;
 
 #[derive(Debug)]
 struct Img {
     image: Vec<u32>,
     width: usize,
     height: usize,
-    objects: BTreeMap<usize, Vec<((usize, usize), FromImage)>>,
-    selected: Option<((usize, usize), FromImage)>,
+    objects: HashMap<Handle, (usize, Object)>,
+    layers: BTreeMap<usize, BTreeSet<Handle>>,
+    ids: HashMap<(usize, usize), Handle>,
+    next: usize,
+    selected: Option<Object>,
+    sprites: HashMap<String, Sprite>,
+    opacity: BTreeMap<usize, u32>,
+    clips: BTreeMap<usize, (usize, usize, usize, usize)>,
+    background: u32,
+    menu: u32,
+    font: text::Font,
+    grid: HashMap<(usize, usize), Vec<Handle>>,
+    dirty: Vec<bool>,
 }
 
+///what an object placed on Img is found by, for as long as it's there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Handle(usize);
+
+///the size of the squares the image is split into. Img sorts selectable objects into them by
+/// where they are, so finding the object at a point only has to look at the objects in its
+/// square, and keeps track of which of them changed, so update only draws those anew.
+const GRID: usize = 32;
+
 impl Img {
     fn new(width: usize, height: usize) -> Img {
         let mut ret = Img {
             image: Vec::new(),
             width,
             height,
-            objects: BTreeMap::new(),
+            objects: HashMap::new(),
+            layers: BTreeMap::new(),
+            ids: HashMap::new(),
+            next: 0,
             selected: None,
+            sprites: HashMap::new(),
+            opacity: BTreeMap::new(),
+            clips: BTreeMap::new(),
+            background: 0x666666,
+            menu: 0xF0F0F0,
+            font: text::Font::new(),
+            grid: HashMap::new(),
+            dirty: Vec::new(),
         };
         for _ in 0..ret.dim() {
             ret.push(0xFFFFFF)
         }
+        ret.dirty = vec![true; ret.squares()];
         ret.set_background(0x666666);
         ret.set_menu(0xF0F0F0);
         ret
@@ -44,330 +94,1595 @@ impl Img {
     fn get_img(&self) -> &[u32] {
         &self.image
     }
+    ///the number of squares of the grid the image is split into.
+    fn squares(&self) -> usize {
+        self.width.div_ceil(GRID) * self.height.div_ceil(GRID)
+    }
+    ///marks the squares the area (x, y, width, height) touches to be drawn anew on the next update.
+    fn mark(&mut self, area: (usize, usize, usize, usize)) {
+        let (x, y, width, height) = area;
+        if width == 0 || height == 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let columns = self.width.div_ceil(GRID);
+        let right = (x + width - 1).min(self.width - 1) / GRID;
+        let bottom = (y + height - 1).min(self.height - 1) / GRID;
+        for cy in y / GRID..=bottom {
+            for cx in x / GRID..=right {
+                self.dirty[cy * columns + cx] = true;
+            }
+        }
+    }
     fn set_background(&mut self, inp: u32) {
-        self.objects.insert(
-            0,
-            vec![(
-                (0, 0),
-                FromImage {
-                    content: vec![vec![inp; self.width]; self.height],
-                    selectable: false,
-                    name: None,
-                },
-            )],
-        );
+        self.background = inp;
+        self.clear(0);
+        let background = FromImage::from_vec(vec![OPAQUE | inp; self.dim()], self.width, false);
+        self.attach(background, (0, 0), Some(0));
     }
+    ///the width of the menu on the left, whatever is left of the window next to a square map.
+    fn menu_width(&self) -> usize {
+        self.width.saturating_sub(self.height)
+    }
+    ///replaces the menu layer with an empty menu of the color, and a line dividing it from the map.
     fn set_menu(&mut self, inp: u32) {
-        self.objects.insert(
-            1,
-            vec![(
-                (0, 0),
-                FromImage::from_vec(
-                    vec![inp; (self.width - self.height) * self.height],
-                    self.width - self.height,
-                    false,
-                ),
-            )],
+        self.menu = inp;
+        self.clear(1);
+        let width = self.menu_width();
+        if width == 0 {
+            return;
+        }
+        self.attach(
+            FromImage::from_vec(vec![OPAQUE | inp; width * self.height], width, false),
+            (0, 0),
+            Some(1),
+        );
+        self.line(
+            (width as f64, 0.0),
+            (width as f64, self.height as f64),
+            Stroke {
+                color: 0x333333,
+                width: 2.0,
+                dash: None,
+            },
+            Some(1),
         );
     }
+    ///changes the size of the image, rebuilding the background and the menu for it.
+    /// Everything else on the menu layer is dropped, the rest stays where it is, cut off
+    /// where it no longer fits.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.image = vec![0xFFFFFF; self.dim()];
+        self.dirty = vec![true; self.squares()];
+        self.set_background(self.background);
+        self.set_menu(self.menu);
+        self.grid.clear();
+        let handles: Vec<Handle> = self.objects.keys().copied().collect();
+        for handle in handles {
+            self.index(handle);
+        }
+    }
 
-    fn attach(&mut self, object: &FromImage, coord: (usize, usize), layer: Option<usize>) {
-        let temp = if let Some(a) = layer {
-            self.objects.entry(a).or_insert(Vec::new())
+    ///removes every object on the layer.
+    fn clear(&mut self, layer: usize) {
+        for handle in self.layers.get(&layer).cloned().unwrap_or_default() {
+            self.remove(handle);
+        }
+    }
+    ///the area (x, y, width, height) things on the layer are kept within: its clip rectangle
+    /// inside the image, or the whole image for None or a layer without one.
+    fn bounds(&self, layer: Option<usize>) -> (usize, usize, usize, usize) {
+        let image = (0, 0, self.width, self.height);
+        match layer.and_then(|a| self.clips.get(&a)) {
+            Some(a) => clip((a.0 as i64, a.1 as i64, a.2, a.3), image).unwrap_or_default(),
+            None => image,
+        }
+    }
+    ///the part (x, y, width, height) of the image the object shows in when on the layer,
+    /// or None if none of it shows or it's hidden.
+    fn visible(
+        &self,
+        layer: Option<usize>,
+        object: &Object,
+    ) -> Option<(usize, usize, usize, usize)> {
+        if object.hidden {
+            None
         } else {
-            if let Some(&a) = self.objects.keys().max() {
-                self.objects.entry(a + 1).or_insert(Vec::new())
-            } else {
-                self.objects.entry(1).or_insert(Vec::new())
+            clip(object.area(), self.bounds(layer))
+        }
+    }
+    ///keeps what's on the layer within area (x, y, width, height) of the image, cutting off
+    /// the rest, or lets it use all of the image with None.
+    fn set_clip(&mut self, layer: usize, area: Option<(usize, usize, usize, usize)>) {
+        let handles = self.layers.get(&layer).cloned().unwrap_or_default();
+        for handle in handles.iter() {
+            self.unindex(*handle);
+            self.mark_object(*handle);
+        }
+        match area {
+            Some(a) => self.clips.insert(layer, a),
+            None => self.clips.remove(&layer),
+        };
+        for handle in handles {
+            self.index(handle);
+            self.mark_object(handle);
+        }
+    }
+    ///marks the part of the image the object shows in to be drawn anew.
+    fn mark_object(&mut self, handle: Handle) {
+        let area = match self.objects.get(&handle) {
+            Some((layer, object)) => self.visible(Some(*layer), object),
+            None => None,
+        };
+        if let Some(a) = area {
+            self.mark(a);
+        }
+    }
+    ///the squares of the grid a selectable object shows in.
+    fn cells(&self, handle: Handle) -> Vec<(usize, usize)> {
+        let (x, y, width, height) = match self.objects.get(&handle) {
+            Some((layer, object)) if object.sprite.selectable => {
+                match self.visible(Some(*layer), object) {
+                    Some(a) => a,
+                    None => return Vec::new(),
+                }
             }
+            _ => return Vec::new(),
         };
-        (*temp).push((coord, object.to_format()));
+        (y / GRID..=(y + height - 1) / GRID)
+            .flat_map(|cy| (x / GRID..=(x + width - 1) / GRID).map(move |cx| (cx, cy)))
+            .collect()
+    }
+    ///adds the object to every square of the grid it shows in, if it's selectable.
+    fn index(&mut self, handle: Handle) {
+        for cell in self.cells(handle) {
+            self.grid.entry(cell).or_default().push(handle);
+        }
     }
+    ///takes the object out of the grid again, before it changes or goes.
+    fn unindex(&mut self, handle: Handle) {
+        for cell in self.cells(handle) {
+            if let Some(a) = self.grid.get_mut(&cell) {
+                a.retain(|x| *x != handle);
+            }
+        }
+    }
+    fn deselect(&mut self) {
+        if let Some(area) = self.selected.take().and_then(|x| self.visible(None, &x)) {
+            self.mark(area);
+        }
+    }
+
+    ///returns the sprite of the bmp file at path, loading it only the first time it's asked for.
+    fn sprite(
+        &mut self,
+        path: &str,
+        selectable: bool,
+    ) -> Result<Sprite, Box<dyn std::error::Error>> {
+        if let Some(a) = self.sprites.get(path) {
+            return Ok(a.clone());
+        }
+        let ret = Rc::new(FromImage::new(path, selectable)?);
+        self.sprites.insert(String::from(path), ret.clone());
+        Ok(ret)
+    }
+    ///sets how much the layer shows, from 0 (not at all) to 1 (as its pixels' alpha says).
+    fn set_opacity(&mut self, layer: usize, opacity: f64) {
+        let opacity = (opacity.clamp(0.0, 1.0) * 255.0).round() as u32;
+        self.opacity.insert(layer, opacity);
+        for handle in self.layers.get(&layer).cloned().unwrap_or_default() {
+            self.mark_object(handle);
+        }
+    }
+    ///attaches an image that is only shown once, as a sprite of its own.
+    fn attach(&mut self, object: FromImage, coord: (usize, usize), layer: Option<usize>) -> Handle {
+        let coord = (coord.0 as i64, coord.1 as i64);
+        self.place(Object::new(Rc::new(object), coord), layer)
+    }
+    ///puts the object on the layer, or on a new layer above all others without one, and returns
+    /// the handle it's kept under. Parts outside the image or the clip rectangle of the layer
+    /// are cut off.
+    fn place(&mut self, object: Object, layer: Option<usize>) -> Handle {
+        let layer = match layer {
+            Some(a) => a,
+            None => self.layers.keys().max().map_or(1, |a| a + 1),
+        };
+        let handle = Handle(self.next);
+        self.next += 1;
+        if let Some(id) = object.id {
+            self.ids.insert((layer, id), handle);
+        }
+        self.layers.entry(layer).or_default().insert(handle);
+        self.objects.insert(handle, (layer, object));
+        self.index(handle);
+        self.mark_object(handle);
+        handle
+    }
+    ///returns the object kept under the handle, if it's still there.
+    fn object(&self, handle: Handle) -> Option<&Object> {
+        self.objects.get(&handle).map(|(_, object)| object)
+    }
+    ///returns the object on the layer that was placed with the game id, the last one if
+    /// there are more.
+    fn find(&self, layer: usize, id: usize) -> Option<Handle> {
+        self.ids.get(&(layer, id)).copied()
+    }
+    ///takes the object off the image, returning it if it was there.
+    fn remove(&mut self, handle: Handle) -> Option<Object> {
+        self.unindex(handle);
+        self.mark_object(handle);
+        let (layer, object) = self.objects.remove(&handle)?;
+        if let Some(id) = object.id {
+            if self.ids.get(&(layer, id)) == Some(&handle) {
+                self.ids.remove(&(layer, id));
+            }
+        }
+        if let Some(a) = self.layers.get_mut(&layer) {
+            a.remove(&handle);
+            if a.is_empty() {
+                self.layers.remove(&layer);
+            }
+        }
+        Some(object)
+    }
+    ///changes the object with change, drawing it anew where it was and where it is after.
+    /// Returns whether there was such an object.
+    fn change(&mut self, handle: Handle, change: impl FnOnce(&mut Object)) -> bool {
+        if !self.objects.contains_key(&handle) {
+            return false;
+        }
+        self.unindex(handle);
+        self.mark_object(handle);
+        if let Some((_, object)) = self.objects.get_mut(&handle) {
+            change(object);
+        }
+        self.index(handle);
+        self.mark_object(handle);
+        true
+    }
+    ///moves the top left corner of the object to pos.
+    fn move_to(&mut self, handle: Handle, pos: (i64, i64)) -> bool {
+        self.change(handle, |x| x.pos = pos)
+    }
+    ///shows the object again after hide.
+    fn show(&mut self, handle: Handle) -> bool {
+        self.change(handle, |x| x.hidden = false)
+    }
+    ///keeps the object without drawing it, or finding it with get_item.
+    fn hide(&mut self, handle: Handle) -> bool {
+        self.change(handle, |x| x.hidden = true)
+    }
+    ///shows the sprite in place of the one the object shows.
+    fn replace(&mut self, handle: Handle, sprite: &Sprite) -> bool {
+        self.change(handle, |x| x.sprite = sprite.clone())
+    }
+    ///the width and height the text takes when written with draw_text.
+    fn text_size(&self, inp: &str, size: usize) -> (usize, usize) {
+        let width = inp
+            .lines()
+            .map(|x| self.font.measure(x, size))
+            .max()
+            .unwrap_or(0);
+        (width, inp.lines().count() * self.font.line_height() * size)
+    }
+    ///writes the text at pos as an object of its own on the layer, with every pixel of the font
+    /// as a square of size pixels. Lines are only broken at line breaks.
+    fn draw_text(
+        &mut self,
+        inp: &str,
+        pos: (usize, usize),
+        color: u32,
+        size: usize,
+        layer: Option<usize>,
+    ) {
+        let text = self
+            .font
+            .render(inp, color, size, None, None, text::Align::Left);
+        self.attach(text, pos, layer);
+    }
+    ///writes the text within area (x, y, width, height), wrapped to its width and aligned in it.
+    /// Lines that don't fit in its height are left out.
+    fn draw_text_box(
+        &mut self,
+        inp: &str,
+        area: (usize, usize, usize, usize),
+        color: u32,
+        size: usize,
+        align: text::Align,
+        layer: Option<usize>,
+    ) {
+        let (x, y, width, height) = area;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let text = self
+            .font
+            .render(inp, color, size, Some(width), Some(height), align);
+        self.attach(text, (x, y), layer);
+    }
+    ///draws a line as an object of its own on the layer, see FromImage::line.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: Stroke, layer: Option<usize>) {
+        let half = (stroke.width / 2.0).ceil();
+        let x = (from.0.min(to.0) - half).max(0.0).floor();
+        let y = (from.1.min(to.1) - half).max(0.0).floor();
+        let width = (from.0.max(to.0) + half).min(self.width as f64 - 1.0) - x + 1.0;
+        let height = (from.1.max(to.1) + half).min(self.height as f64 - 1.0) - y + 1.0;
+        if width < 1.0 || height < 1.0 {
+            return;
+        }
+        let mut canvas = FromImage::blank(width as usize, height as usize);
+        canvas.line((from.0 - x, from.1 - y), (to.0 - x, to.1 - y), stroke);
+        self.attach(canvas, (x as usize, y as usize), layer);
+    }
+    ///draws the squares of the image that changed since the last update anew, layer by layer
+    /// and the selection box on top, leaving the rest of the image as it was. Every pixel is
+    /// laid over what's below it by its alpha and the opacity of its layer.
     fn update(&mut self) {
-        for (_, objects) in self.objects.iter() {
-            for ((x, y), object) in objects {
-                // println!("{} {}", object.len(), object[0].len());
-                for i in 0..object.len() {
-                    for j in 0..object[0].len() {
-                        if object[i][j] / 0x1000000 < 1 && object[i][j] != 0xFEFEFE {
-                            self.image[(*y + i) * self.width + (*x + j)] = object[i][j];
-                        }
-                    }
+        let columns = self.width.div_ceil(GRID);
+        // every object with the part of the image it shows in and the opacity of its layer
+        let mut objects = Vec::new();
+        for (layer, handles) in self.layers.iter() {
+            let opacity = self.opacity.get(layer).copied().unwrap_or(0xFF);
+            if opacity == 0 {
+                continue;
+            }
+            for handle in handles {
+                let object = &self.objects[handle].1;
+                if let Some(area) = self.visible(Some(*layer), object) {
+                    objects.push((object, area, opacity));
                 }
             }
         }
-        if let Some(((x, y), object)) = &self.selected {
-            for i in 0..object.len() {
-                for j in 0..object[0].len() {
-                    if j == 49 {
-                        // println!("{:.x}", object[i][j]);
+        if let Some(object) = &self.selected {
+            if let Some(area) = clip(object.area(), self.bounds(None)) {
+                objects.push((object, area, 0xFF));
+            }
+        }
+        for (object, (x, y, width, height), opacity) in objects {
+            let (ox, oy) = object.pos;
+            let (right, bottom) = (x + width, y + height);
+            for cy in y / GRID..=(bottom - 1) / GRID {
+                for cx in x / GRID..=(right - 1) / GRID {
+                    if !self.dirty[cy * columns + cx] {
+                        continue;
                     }
-                    if object[i][j] / 0x1000000 < 1 && object[i][j] != 0xFEFEFE {
-                        self.image[(*y + i) * self.width + (*x + j)] = object[i][j];
-                        // println!("{} {} {}", object[i][j], i, j);
+                    for i in y.max(cy * GRID)..bottom.min((cy + 1) * GRID) {
+                        for j in x.max(cx * GRID)..right.min((cx + 1) * GRID) {
+                            let val =
+                                object.sprite[(i as i64 - oy) as usize][(j as i64 - ox) as usize];
+                            if val >> 24 == 0 {
+                                continue;
+                            }
+                            let val = object.tint.map_or(val, |a| tint(val, a));
+                            let below = &mut self.image[i * self.width + j];
+                            *below = over(*below, val, opacity);
+                        }
                     }
                 }
             }
         }
+        self.dirty.iter_mut().for_each(|x| *x = false);
     }
+    ///draws a box around the area, taking the place of the last one.
     fn select(&mut self, input: ((usize, usize), (usize, usize))) {
         let (pos, dim) = input;
-        let mut ret = Vec::new();
-        ret.push(vec![0x0; dim.0]);
-        let mut temp = vec![0x0];
-        temp.append(&mut vec![0x1000000; dim.0 - 2]);
-        temp.push(0x0);
-        for _ in 2..dim.1 {
-            ret.push(temp.clone());
-        }
-        ret.push(vec![0x0; dim.0]);
-        self.selected = Some((
-            pos,
-            FromImage {
-                content: ret,
-                selectable: false,
-                name: None,
-            },
-        ));
-    }
-
-    fn get_item(&self, pos: (usize, usize)) -> Option<(usize, usize)> {
-        let mut i = 0;
-        for (j, vals) in self.objects.iter().rev() {
-            for ((x, y), object) in vals {
-                if pos.0 < object[0].len() + x
-                    && pos.0 > *x
-                    && pos.1 < object.len() + y
-                    && pos.1 > *y
-                    && object.selectable
-                {
-                    if let Some(a) = object.get_name() {
-                        println!("{}", a);
-                    }
-                    return Some((*j, i));
-                }
-                i += 1;
+        self.deselect();
+        if dim.0 < 2 || dim.1 < 2 {
+            return;
+        }
+        let mut ret = FromImage::blank(dim.0, dim.1);
+        for x in 0..dim.0 {
+            ret[0][x] = OPAQUE;
+            ret[dim.1 - 1][x] = OPAQUE;
+        }
+        for y in 0..dim.1 {
+            ret[y][0] = OPAQUE;
+            ret[y][dim.0 - 1] = OPAQUE;
+        }
+        self.mark((pos.0, pos.1, dim.0, dim.1));
+        self.selected = Some(Object::new(Rc::new(ret), (pos.0 as i64, pos.1 as i64)));
+    }
+
+    ///returns the selectable object showing at pos. Where objects overlap, the one on the
+    /// highest layer wins, and of those the one placed first.
+    fn get_item(&self, pos: (usize, usize)) -> Option<Handle> {
+        self.grid
+            .get(&(pos.0 / GRID, pos.1 / GRID))?
+            .iter()
+            .filter(|x| {
+                let (layer, object) = &self.objects[x];
+                let (x, y, width, height) = self.visible(Some(*layer), object).unwrap_or_default();
+                pos.0 >= x && pos.0 < x + width && pos.1 >= y && pos.1 < y + height
+            })
+            .max_by_key(|x| (self.objects[x].0, Reverse(**x)))
+            .copied()
+    }
+    ///returns the text written in a framed box, to show next to the mouse with beside.
+    fn tooltip(&self, inp: &str) -> Option<Sprite> {
+        let text = self
+            .font
+            .render(inp, 0x000000, 1, None, None, text::Align::Left);
+        if text.len() == 0 {
+            return None;
+        }
+        let (width, height) = (text.width() + 8, text.len() + 8);
+        let mut ret = FromImage::from_vec(vec![OPAQUE | 0x333333; width * height], width, false);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let inside = (4..text.width() + 4).contains(&x) && (4..text.len() + 4).contains(&y);
+                ret[y][x] = OPAQUE
+                    | if inside {
+                        over(0xFFFFE8, text[y - 4][x - 4], 0xFF)
+                    } else {
+                        0xFFFFE8
+                    };
             }
-            i = 0;
         }
+        Some(Rc::new(ret))
+    }
+    ///returns where to put the top left corner of the object to show it next to pos, below and
+    /// to the right of it if there is room, otherwise above or to the left.
+    fn beside(&self, pos: (usize, usize), handle: Handle) -> (i64, i64) {
+        let (width, height) = self
+            .object(handle)
+            .map_or((0, 0), |x| (x.sprite.width(), x.sprite.len()));
+        let place = |at: usize, size: usize, room: usize| {
+            if at + 16 + size <= room {
+                at + 16
+            } else {
+                at.saturating_sub(size + 4).min(room.saturating_sub(size))
+            }
+        };
+        (
+            place(pos.0, width, self.width) as i64,
+            place(pos.1, height, self.height) as i64,
+        )
+    }
+    ///writes the image as it was last updated to a bmp file.
+    fn save_bmp(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.bmp().save(path)?;
+        Ok(())
+    }
+    ///returns the image as it was last updated as a bmp image.
+    fn bmp(&self) -> bmp::Image {
+        let mut ret = bmp::Image::new(self.width as u32, self.height as u32);
+        for (x, y) in ret.coordinates() {
+            let val = self.image[y as usize * self.width + x as usize];
+            ret.set_pixel(
+                x,
+                y,
+                bmp::Pixel::new((val >> 16) as u8, (val >> 8) as u8, val as u8),
+            );
+        }
+        ret
+    }
+    ///writes the image as it was last updated, as png if the path ends with .png, else as bmp.
+    fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if path.to_lowercase().ends_with(".png") {
+            self.save_png(path)
+        } else {
+            self.save_bmp(path)
+        }
+    }
+    ///writes the image as it was last updated to a png file, as 8 bit rgb.
+    fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.png()?)?;
+        Ok(())
+    }
+    ///returns the image as it was last updated as the bytes of a png file, as 8 bit rgb.
+    fn png(&self) -> std::io::Result<Vec<u8>> {
+        let mut rows = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.image.chunks(self.width) {
+            // every row starts with its filter type, and none is used
+            rows.push(0);
+            for val in row {
+                rows.extend_from_slice(&[(val >> 16) as u8, (val >> 8) as u8, *val as u8]);
+            }
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&rows)?;
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, data) in [
+            (b"IHDR", header),
+            (b"IDAT", encoder.finish()?),
+            (b"IEND", Vec::new()),
+        ] {
+            let mut crc = crc32fast::Hasher::new();
+            crc.update(kind);
+            crc.update(&data);
+            out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            out.extend_from_slice(kind);
+            out.extend_from_slice(&data);
+            out.extend_from_slice(&crc.finalize().to_be_bytes());
+        }
+        Ok(out)
+    }
+    ///returns (position, width/height) of the part of the object that shows.
+    fn obdim(&self, handle: Handle) -> ((usize, usize), (usize, usize)) {
+        let (x, y, width, height) = match self.objects.get(&handle) {
+            Some((layer, object)) => self.visible(Some(*layer), object).unwrap_or_default(),
+            None => (0, 0, 0, 0),
+        };
+        ((x, y), (width, height))
+    }
+}
+
+///how to draw a line: its color, its width in pixels, and for dashed lines
+/// the length of each drawn part and of each gap between them.
+#[derive(Debug, Clone, Copy)]
+struct Stroke {
+    color: u32,
+    width: f64,
+    dash: Option<(f64, f64)>,
+}
+
+///a shared handle to an image, so it's only kept once however many objects show it.
+type Sprite = Rc<FromImage>;
+
+///an image placed on Img: the sprite it shows, where its top left corner is, and what is
+/// particular to this one of the objects showing the sprite. With a tint its colors are
+/// multiplied with the tint's, so a white sprite can be shown in any color, and id is the id
+/// in the game of what it shows, such as a system, to find it by.
+#[derive(Debug, Clone, PartialEq)]
+struct Object {
+    pos: (i64, i64),
+    sprite: Sprite,
+    tint: Option<u32>,
+    id: Option<usize>,
+    name: Option<String>,
+    hidden: bool,
+}
+
+impl Object {
+    fn new(sprite: Sprite, pos: (i64, i64)) -> Object {
+        Object {
+            pos,
+            sprite,
+            tint: None,
+            id: None,
+            name: None,
+            hidden: false,
+        }
+    }
+    ///the area (x, y, width, height) the object covers, which may reach outside the image.
+    fn area(&self) -> (i64, i64, usize, usize) {
+        (
+            self.pos.0,
+            self.pos.1,
+            self.sprite.width(),
+            self.sprite.len(),
+        )
+    }
+}
+
+///returns the part (x, y, width, height) of area that lies within bounds, or None if none does.
+fn clip(
+    area: (i64, i64, usize, usize),
+    bounds: (usize, usize, usize, usize),
+) -> Option<(usize, usize, usize, usize)> {
+    let (x, y, width, height) = area;
+    let (bx, by, bw, bh) = (
+        bounds.0 as i64,
+        bounds.1 as i64,
+        bounds.2 as i64,
+        bounds.3 as i64,
+    );
+    let (left, top) = (x.max(bx), y.max(by));
+    let right = (x + width as i64).min(bx + bw);
+    let bottom = (y + height as i64).min(by + bh);
+    if left < right && top < bottom {
+        Some((
+            left as usize,
+            top as usize,
+            (right - left) as usize,
+            (bottom - top) as usize,
+        ))
+    } else {
         None
     }
-    ///returns (position, width/height)
-    fn obdim(&self, id: usize, pos: usize) -> ((usize, usize), (usize, usize)) {
-        let (pos, a) = &self.objects[&id][pos];
-        // println!("{}, {}, {}, {}", a[0].len(), pos.0, a.len(), pos.1);
-        let ext = (a[0].len(), a.len());
-        (*pos, ext)
+}
+
+///multiplies the color channels of the pixel with those of the 0xRRGGBB tint, keeping its
+/// alpha, so white becomes the tint.
+fn tint(val: u32, tint: u32) -> u32 {
+    let channel = |shift: u32| (((val >> shift) & 0xFF) * ((tint >> shift) & 0xFF) / 0xFF) << shift;
+    (val & OPAQUE) + channel(16) + channel(8) + channel(0)
+}
+
+///lays the 0xAARRGGBB pixel over the 0xRRGGBB color below it, weighing it by its alpha times
+/// opacity, from 0 to 255. Returns the 0xRRGGBB color that shows.
+fn over(below: u32, val: u32, opacity: u32) -> u32 {
+    let alpha = (val >> 24) * opacity / 0xFF;
+    let channel = |shift: u32| {
+        let (a, b) = ((below >> shift) & 0xFF, (val >> shift) & 0xFF);
+        ((a * (0xFF - alpha) + b * alpha + 0x7F) / 0xFF) << shift
+    };
+    channel(16) + channel(8) + channel(0)
+}
+
+///the alpha of a pixel covering what's below it completely, pixels are 0xAARRGGBB.
+const OPAQUE: u32 = 0xFF000000;
+///a pixel that doesn't show at all.
+const CLEAR: u32 = 0;
+///the color bmp files without alpha use for pixels that are see-through.
+const KEY: u32 = 0xFEFEFE;
+
+///reads the pixels of an uncompressed 32 bit bmp file, which keeps alpha the bmp crate leaves
+/// out, as 0xAARRGGBB and the width of the image. Returns None for any other kind of file.
+/// Files that set no alpha at all are taken to be opaque.
+fn read_argb(data: &[u8]) -> Option<(Vec<u32>, usize)> {
+    let int = |at: usize| {
+        let a = data.get(at..at + 4)?;
+        Some(u32::from_le_bytes([a[0], a[1], a[2], a[3]]))
+    };
+    if data.get(0..2)? != b"BM" || data.get(28..30)? != [32, 0] {
+        return None;
+    }
+    let (offset, header) = (int(10)? as usize, int(14)?);
+    let (width, height) = (int(18)? as i32, int(22)? as i32);
+    let masks = match int(30)? {
+        0 => [0xFF0000, 0xFF00, 0xFF, OPAQUE],
+        3 if header < 56 => [int(54)?, int(58)?, int(62)?, 0],
+        3 | 6 => [int(54)?, int(58)?, int(62)?, int(66)?],
+        _ => return None,
+    };
+    // every channel is scaled to 8 bits, and a missing alpha means opaque
+    let channel = |val: u32, mask: u32| match mask {
+        0 => 0xFF,
+        a => ((val & a) >> a.trailing_zeros()) * 0xFF / (a >> a.trailing_zeros()),
+    };
+    let (w, h) = (
+        width.unsigned_abs() as usize,
+        height.unsigned_abs() as usize,
+    );
+    let mut ret = Vec::with_capacity(w * h);
+    for y in 0..h {
+        // rows are kept from the bottom up, unless the height is negative
+        let row = if height < 0 { y } else { h - 1 - y };
+        for x in 0..w {
+            let val = int(offset + (row * w + x) * 4)?;
+            ret.push(
+                channel(val, masks[3]) << 24
+                    | channel(val, masks[0]) << 16
+                    | channel(val, masks[1]) << 8
+                    | channel(val, masks[2]),
+            );
+        }
+    }
+    if ret.iter().all(|x| x >> 24 == 0) {
+        ret.iter_mut().for_each(|x| *x |= OPAQUE);
     }
+    Some((ret, w))
 }
 
+///an image kept row after row in one flat buffer, indexed as image[y][x].
+/// Pixels are 0xAARRGGBB, from CLEAR to OPAQUE alpha.
 #[derive(Debug, Eq, Hash, PartialEq, Clone)]
 struct FromImage {
-    content: Vec<Vec<u32>>,
+    content: Vec<u32>,
+    width: usize,
     selectable: bool,
-    name: Option<String>,
 }
 
 impl FromImage {
+    ///reads a bmp file. 32 bit ones keep their alpha, in others pixels of the KEY color are
+    /// see-through and the rest opaque.
     fn new(inp: &str, selectable: bool) -> Result<FromImage, Box<dyn std::error::Error>> {
+        let data = fs::read(inp)?;
+        if let Some((content, width)) = read_argb(&data) {
+            return Ok(FromImage::from_vec(content, width, selectable));
+        }
+        let inp = bmp::from_reader(&mut &data[..])?;
         let mut content = Vec::new();
-
-        let inp = bmp::open(inp).expect("OK");
-        let width = inp.get_width() - 1;
-
-        let mut pix: bmp::Pixel;
-        let mut temp = Vec::new();
         for (x, y) in inp.coordinates() {
-            pix = inp.get_pixel(x, y);
-            temp.push((pix.r as u32 * 256 * 256) + (pix.g as u32 * 256) + (pix.b as u32));
-            if x == width {
-                content.push(temp.clone());
-                temp.clear();
-            }
+            let pix = inp.get_pixel(x, y);
+            content.push(
+                match (pix.r as u32 * 256 * 256) + (pix.g as u32 * 256) + (pix.b as u32) {
+                    KEY => CLEAR,
+                    a => OPAQUE | a,
+                },
+            );
         }
-        Ok(FromImage {
+        Ok(FromImage::from_vec(
             content,
+            inp.get_width() as usize,
             selectable,
-            name: None,
-        })
+        ))
     }
-    fn from_vec(content2: Vec<u32>, width: usize, selectable: bool) -> FromImage {
-        let mut content = Vec::new();
-        for i in 0..content2.len() / width {
-            content.push((0..width).map(|x| content2[x + i * width]).collect())
-        }
+    ///takes the pixels as rows of width, leaving out an unfinished last row.
+    fn from_vec(mut content: Vec<u32>, width: usize, selectable: bool) -> FromImage {
+        content.truncate(content.len() / width * width);
         FromImage {
             content,
+            width,
             selectable,
-            name: None,
         }
     }
-    fn len(&self) -> usize {
-        self.content.len()
+    ///returns a see-through image to draw on.
+    fn blank(width: usize, height: usize) -> FromImage {
+        FromImage {
+            content: vec![CLEAR; width * height],
+            width,
+            selectable: false,
+        }
     }
-    fn to_format(&self) -> FromImage {
-        self.clone()
+    fn width(&self) -> usize {
+        self.width
     }
-    fn set_name(&mut self, name: Option<String>) {
-        self.name = name;
+    ///draws a line between two points, coloring every pixel that lies within half the stroke
+    /// width of it. A width of 1 gives the same unbroken one pixel line as Bresenham's.
+    /// The parts of the line outside the image are left out.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: Stroke) {
+        if self.content.is_empty() {
+            return;
+        }
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let half = (stroke.width / 2.0).max(0.5);
+        let x0 = (from.0.min(to.0) - half).floor().max(0.0) as usize;
+        let y0 = (from.1.min(to.1) - half).floor().max(0.0) as usize;
+        let x1 = ((from.0.max(to.0) + half).ceil().max(-1.0) + 1.0) as usize;
+        let y1 = ((from.1.max(to.1) + half).ceil().max(-1.0) + 1.0) as usize;
+        for y in y0..y1.min(self.len()) {
+            for x in x0..x1.min(self.width) {
+                let (px, py) = (x as f64 - from.0, y as f64 - from.1);
+                let t = if length > 0.0 {
+                    ((px * dx + py * dy) / (length * length)).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                if (px - t * dx).powi(2) + (py - t * dy).powi(2) > half * half {
+                    continue;
+                }
+                if let Some((on, off)) = stroke.dash {
+                    if (t * length) % (on + off) >= on {
+                        continue;
+                    }
+                }
+                self[y][x] = OPAQUE | stroke.color;
+            }
+        }
     }
-    fn get_name(&self) -> &Option<String> {
-        &self.name
+    ///the height of the image, in rows.
+    fn len(&self) -> usize {
+        match self.width {
+            0 => 0,
+            a => self.content.len() / a,
+        }
     }
 }
 
 impl std::ops::Index<usize> for FromImage {
-    type Output = Vec<u32>;
+    type Output = [u32];
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.content[index]
+        &self.content[index * self.width..(index + 1) * self.width]
     }
 }
 
-// #[derive(Debug, Clone)]
-// pub struct GalObject {
-//     id: usize,
-//     x: i64,
-//     y: i64,
-//     typ: String,
-//     name: String,
-//     planets: Vec<usize>
-// }
+impl std::ops::IndexMut<usize> for FromImage {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.content[index * self.width..(index + 1) * self.width]
+    }
+}
 
 fn make_shit(mut w: usize, mut h: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let smiley_tester = &FromImage::from_vec(
-        vec![
-            0x1000000, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0x1000000, 0xFFFFFF, 0x0,
-            0x0, 0xFFFFFF, 0x0, 0x0, 0xFFFFFF, 0xFFFFFF, 0x0, 0x0, 0xFFFFFF, 0x0, 0x0, 0xFFFFFF,
-            0xFFFFFF, 0x0, 0x0, 0xFFFFFF, 0x0, 0x0, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF,
-            0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF,
-            0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0x0, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0x0,
-            0xFFFFFF, 0xFFFFFF, 0x0, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0x0, 0xFFFFFF, 0xFFFFFF, 0x0,
-            0x0, 0x0, 0x0, 0x0, 0xFFFFFF, 0x1000000, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF,
-            0xFFFFFF, 0x1000000,
-        ],
-        7,
-        true,
-    );
-
     let a = saveread::reader()?;
     println!("finished reading");
 
-    let (_, galaxy) = a.get_obj_iter().next().unwrap();
-    let mut objit = galaxy.get_obj_iter(); // iterator over GalObjects ( in vector )
-    let mut maxx = 0f64;
-    let mut minx = 0f64;
-    let mut maxy = 0f64;
-    let mut miny = 0f64;
-    while let Some(a) = objit.next() {
-        if a.gx() > maxx {
-            maxx = a.gx();
-        } else if a.gx() < minx {
-            minx = a.gx();
-        }
-        if a.gy() > maxy {
-            maxy = a.gy();
-        } else if a.gy() < miny {
-            miny = a.gy();
-        }
-    }
-    let scale = 0.9 * (h as f64) / (maxx - minx).max(maxy - miny);
-    let mx = -minx - 0.05 * (h as f64) + 300f64;
-    let my = -miny - 0.05 * (h as f64);
-    println!("{} {} {} {}", minx, maxx, miny, maxy);
-    println!("{} {} {}", scale, mx, my);
+    let mut timeline = timeline::Timeline::new(a.dates(), (0, 0, 0, 0))?;
+    let mut galaxy = &a[timeline.date()];
 
     if h > w {
         std::mem::swap(&mut w, &mut h);
     }
-    let star = &mut FromImage::new("./data/star.bmp", true)?;
-    let ua = &mut FromImage::new("./letters/ua.bmp", false)?;
-    let la = &mut FromImage::new("./letters/la.bmp", false)?;
-    let ln = &mut FromImage::new("./letters/ln.bmp", false)?;
-
     let mut img = Img::new(w, h);
 
     let mut window = Window::new(
-        "test",
+        &galaxy.date_legible(),
         img.width(),
         img.height(),
         WindowOptions {
-            resize: false,
+            resize: true,
             ..WindowOptions::default()
         },
     )?;
-    img.attach(ua, (10, 10), Some(3));
-    img.attach(la, (18, 10), Some(3));
-    img.attach(la, (26, 10), Some(3));
-    img.attach(ln, (34, 10), Some(3));
+    // tooltips let a little of the map through, and the heatmap more of the territory under it
+    img.set_opacity(3, 0.8);
+    img.set_opacity(10, 0.9);
+    let typed = ui::Typed::default();
+    window.set_input_callback(Box::new(typed.clone()));
 
+    let table = colors::ColorTable::find()?;
+    let bindings = keys::Bindings::find()?;
+    let view = |img: &Img| {
+        (
+            img.menu_width(),
+            0,
+            img.width() - img.menu_width(),
+            img.height(),
+        )
+    };
+    // the slider sits at the bottom of the menu, or of the map when there is no room for a menu
+    let slider = |img: &Img| match img.menu_width() {
+        a if a >= 80 => (10, img.height().saturating_sub(30), a - 20, 20),
+        _ => (
+            10,
+            img.height().saturating_sub(30),
+            img.width().saturating_sub(20),
+            20,
+        ),
+    };
+    // the search field in the menu, which the search key puts the keys in
+    let search_area = |img: &Img| (10, 166, img.menu_width().saturating_sub(20), 20);
+    timeline.resize(slider(&img));
+    let mut home = map::Camera::fit(galaxy.maxc(), view(&img));
+    let mut camera = home;
+    let mut selected: Option<usize> = None;
+    let mut style = map::Style {
+        labels: map::Labels::All,
+        mode: map::Mode::Owner,
+        heat: None,
+    };
+    // what the heatmap can be made of in the galaxy shown
+    let mut filters: Vec<saveread::Filter> = Vec::new();
+    // the territory and heatmaps drawn lately, kept for when the map is only moved
+    let mut cache = map::Cache::new(img.sprite("./data/star.bmp", true)?);
+    // where the left mouse button was last seen while held, and whether it has moved enough
+    // since being pressed to count as dragging the map instead of clicking it
+    let mut drag: Option<(f32, f32)> = None;
+    let mut dragged = false;
+    // the system under the mouse and where the mouse was, its tooltip is on layer 10
+    let mut hover: Option<(usize, (usize, usize))> = None;
+    let mut tip: Option<Handle> = None;
+    // the controls in the menu go on layer 11, and the lists of dropdowns on 12
+    let mut ui = ui::Ui::new(11);
+    let mut last_input = ui::Input::default();
+    let mut tooltips = true;
+    // whether the help with the key bindings is shown, on layer 13
+    let mut help = false;
+    let mut territory = true;
+    let mut hyperlanes = true;
+    let mut search = String::new();
+    // what the last key did, if the map doesn't show it, e.g. where a screenshot went
+    let mut notice = String::new();
+    let saves: Vec<String> = a.dates().iter().map(|x| a[*x].date_legible()).collect();
+    let names: Vec<String> = map::Labels::ALL
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect();
+    let modes: Vec<String> = map::Mode::ALL
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect();
 
-    let mut objit = galaxy.get_obj_iter();
-    while let Some(a) = objit.next() {
-        let x = ((a.gx() * scale) + mx) as usize;
-        let y = ((a.gy() * scale) + my) as usize;
-        star.set_name(Some(String::from(a.get_name())));
-        img.attach(star, (x, y), Some(3));
-    }
-    star.name = None;
-
-    let mut TEMP = 0;
+    // playback speed in days a second; while recording, every frame is 1/50 of a second
+    let mut speed = 360.0;
+    let mut recording = false;
+    let mut frame = 0;
+    let mut last = Instant::now();
 
     let mut keep = true;
     let mut change = true;
+    let mut moved = true;
     while window.is_open() && keep {
-        // img.resize(window.get_size());
         window.limit_update_rate(Some(Duration::from_millis(20)));
+        let (width, height) = window.get_size();
+        if (width, height) != (img.width(), img.height()) && width > 0 && height > 0 {
+            img.resize(width, height);
+            home = map::Camera::fit(galaxy.maxc(), view(&img));
+            camera.resize(view(&img));
+            timeline.resize(slider(&img));
+            moved = true;
+        }
+        let elapsed = last.elapsed().as_secs_f64();
+        last = Instant::now();
+        let played = timeline.advance(if recording {
+            speed / 50.0
+        } else {
+            speed * elapsed
+        });
+        moved |= played;
+        let coordinates = window.get_mouse_pos(MouseMode::Clamp);
+        let input = ui::Input {
+            mouse: coordinates.map_or((0, 0), |(x, y)| (x as usize, y as usize)),
+            down: window.get_mouse_down(MouseButton::Left),
+            scroll: window.get_scroll_wheel().map_or(0.0, |(_, y)| y),
+            keys: window.get_keys_pressed(KeyRepeat::Yes).unwrap_or_default(),
+            pressed: window.get_keys_pressed(KeyRepeat::No).unwrap_or_default(),
+            text: typed.take(),
+        };
+        // keys only go to the text input while it has them
+        let typing = ui.typing();
+        let redraw = moved;
+        if moved {
+            galaxy = &a[timeline.date()];
+            // the title tells the playback speed, which the slider only shows while playing
+            window.set_title(&format!(
+                "{} ({} days/s{})",
+                galaxy.date_legible(),
+                speed,
+                if recording { ", recording" } else { "" }
+            ));
+            let next = timeline.blend().map(|(date, factor)| (&a[date], factor));
+            let legend = map::draw(&mut img, galaxy, next, &table, &camera, &style, &mut cache);
+            filters = galaxy.filters();
+            if let Some(a) = style.heat.as_ref().filter(|x| !filters.contains(x)) {
+                filters.push(a.clone());
+            }
+            match selected.and_then(|a| img.find(5, a)) {
+                Some(handle) => img.select(img.obdim(handle)),
+                None => img.deselect(),
+            }
+            img.clear(7);
+            let (x, y, width, height) = timeline.area();
+            if width > 0 && height > 0 {
+                img.attach(timeline.draw(), (x, y), Some(7));
+            }
+            // the date heads the menu, and the details of the selected system fill it down to the slider
+            img.clear(6);
+            img.clear(8);
+            let width = img.menu_width().saturating_sub(20);
+            if width > 0 {
+                img.draw_text(&galaxy.date_legible(), (10, 10), 0x000000, 2, Some(6));
+            }
+            // the controls take the top of the menu when it's wide enough for them
+            let top = if img.menu_width() >= 160 { 284 } else { 40 };
+            // the legend of the map mode goes at the bottom, above the slider
+            let mut bottom = y.saturating_sub(20);
+            if width > 0 {
+                let row = img.text_size("A", 1).1 + 3;
+                let mut heading = format!("Map: {}", style.mode.name());
+                if let Some(a) = &style.heat {
+                    heading.push_str(&format!("\nHeat: {}", a));
+                }
+                if !notice.is_empty() {
+                    heading = format!("{}\n{}", notice, heading);
+                }
+                let lines = heading.lines().count();
+                bottom = bottom.saturating_sub((legend.len() + lines) * row);
+                img.draw_text_box(
+                    &heading,
+                    (10, bottom, width, lines * row),
+                    0x000000,
+                    1,
+                    text::Align::Left,
+                    Some(6),
+                );
+                for (i, (label, color)) in legend.iter().enumerate() {
+                    let line = bottom + (i + lines) * row;
+                    if let Some(color) = color {
+                        let swatch = vec![OPAQUE | color; 64];
+                        img.attach(FromImage::from_vec(swatch, 8, false), (10, line), Some(6));
+                    }
+                    img.draw_text_box(
+                        label,
+                        (22, line, width.saturating_sub(12), row),
+                        0x000000,
+                        1,
+                        text::Align::Left,
+                        Some(6),
+                    );
+                }
+            }
+            if let Some(lines) = selected.and_then(|a| galaxy.details(a)) {
+                let area = (10, top, width, bottom.saturating_sub(top + 8));
+                img.draw_text_box(
+                    &lines.join("\n"),
+                    area,
+                    0x000000,
+                    1,
+                    text::Align::Left,
+                    Some(8),
+                );
+            }
+            // over the slider go its first and last date, and whether it's playing or recording
+            let (first, last) = timeline.range();
+            let area = (x, y.saturating_sub(14), width, 12);
+            let state = match (timeline.playing(), recording) {
+                (_, true) => format!("recording, {} days/s", speed),
+                (true, false) => format!("{} days/s", speed),
+                (false, false) => String::new(),
+            };
+            for (inp, align) in [
+                (a[first].date_legible(), text::Align::Left),
+                (state, text::Align::Center),
+                (a[last].date_legible(), text::Align::Right),
+            ] {
+                img.draw_text_box(&inp, area, 0x333333, 1, align, Some(6));
+            }
+            // the help goes in the middle of the map, over everything else on it
+            img.clear(13);
+            // searching needs the search field, which only a menu wide enough for the controls has
+            let without = if img.menu_width() >= 160 {
+                vec![]
+            } else {
+                vec![Action::Search]
+            };
+            let keys = format!("Keys:\n{}", bindings.help(&without).join("\n"));
+            if let Some(sprite) = help.then(|| img.tooltip(&keys)).flatten() {
+                let (x, y, width, height) = camera.view();
+                let coord = (
+                    (x + width / 2) as i64 - sprite.width() as i64 / 2,
+                    (y + height / 2) as i64 - sprite.len() as i64 / 2,
+                );
+                img.place(Object::new(sprite, coord), Some(13));
+            }
+            // the map may have changed under the mouse, so the tooltip is made anew
+            hover = None;
+            moved = false;
+            change = true;
+        }
+        // the controls are only drawn anew when something happened that could change them
+        if redraw || input != last_input || input.events() {
+            ui.begin(&mut img, input.clone());
+            let width = img.menu_width().saturating_sub(20);
+            if img.menu_width() >= 160 {
+                let half = (width - 6) / 2;
+                let play = if timeline.playing() { "Pause" } else { "Play" };
+                if ui.button(&mut img, play, (10, 40, half, 20)) {
+                    timeline.play();
+                    moved = true;
+                }
+                if ui.button(&mut img, "Home", (16 + half, 40, width - half - 6, 20)) {
+                    camera = home;
+                    moved = true;
+                }
+                let area = (10, 66, width, 16);
+                if ui.checkbox(&mut img, "Tooltips", &mut tooltips, area) {
+                    hover = None;
+                    moved = true;
+                }
+                let area = search_area(&img);
+                let entered = ui.text_input(&mut img, &mut search, "search", area);
+                // while searching, the results take the place of the saves, and enter picks the best
+                let mut picked = None;
+                let mut results = Vec::new();
+                if search.trim().is_empty() {
+                    let (_, current) = timeline.dates();
+                    let mut chosen = Some(current);
+                    if ui.list(&mut img, &saves, &mut chosen, (10, 192, width, 84)) {
+                        moved |= chosen.is_some_and(|x| timeline.select(x));
+                    }
+                } else {
+                    results = search::find(galaxy, &search);
+                    let labels: Vec<String> = results.iter().map(|x| x.0.clone()).collect();
+                    let mut chosen = None;
+                    if ui.list(&mut img, &labels, &mut chosen, (10, 192, width, 84)) {
+                        picked = chosen;
+                    } else if entered {
+                        picked = Some(0);
+                    }
+                }
+                if let Some((_, id)) = picked.and_then(|x| results.get(x)) {
+                    let found = galaxy.get_obj_iter().find(|x| x.id() == *id);
+                    if let Some(system) = found {
+                        camera.look_at((system.gx(), system.gy()), 4.0);
+                    }
+                    selected = Some(*id);
+                    moved = true;
+                }
+                // the dropdowns come last, so their lists are drawn over the controls under them
+                let mut items = vec![String::from("No heatmap")];
+                items.extend(filters.iter().map(|x| x.to_string()));
+                let mut i = match &style.heat {
+                    Some(a) => filters.iter().position(|x| x == a).map_or(0, |x| x + 1),
+                    None => 0,
+                };
+                if ui.dropdown(&mut img, &items, &mut i, (10, 140, width, 20)) {
+                    style.heat = i.checked_sub(1).and_then(|x| filters.get(x)).cloned();
+                    moved = true;
+                }
+                let mut i = map::Mode::ALL
+                    .iter()
+                    .position(|x| *x == style.mode)
+                    .unwrap_or(0);
+                if ui.dropdown(&mut img, &modes, &mut i, (10, 114, width, 20)) {
+                    style.mode = map::Mode::ALL[i];
+                    moved = true;
+                }
+                let mut i = map::Labels::ALL
+                    .iter()
+                    .position(|x| *x == style.labels)
+                    .unwrap_or(0);
+                if ui.dropdown(&mut img, &names, &mut i, (10, 88, width, 20)) {
+                    style.labels = map::Labels::ALL[i];
+                    moved = true;
+                }
+            }
+            ui.end();
+            last_input = input.clone();
+            change = true;
+        }
         if change {
             img.update();
+            if recording && played {
+                img.save_bmp(&format!("frames/{:05}.bmp", frame))?;
+                frame += 1;
+            }
             window.update_with_buffer(img.get_img(), img.width(), img.height())?;
-        // gotta switch so it only updates when needed
         } else {
             window.update();
         }
         change = false;
 
-        if let Some(coordinates) = window.get_mouse_pos(MouseMode::Clamp) {
+        if let Some(coordinates) = coordinates {
             let (x, y) = (coordinates.0 as usize, coordinates.1 as usize);
+            let mouse = (coordinates.0 as f64, coordinates.1 as f64);
 
-            if window.get_mouse_down(MouseButton::Left) {
-                // img.attach(smiley_tester, (x, y), Some(2));
-                // img.select((x, y), (50, 100));
-                if let Some((id, pos)) = img.get_item((x, y)) {
-                    // let a = img.obdim(id, pos);
-                    img.select(img.obdim(id, pos));
+            if input.down {
+                match drag {
+                    None => {
+                        drag = Some(coordinates);
+                        dragged = timeline.contains(mouse);
+                        if dragged {
+                            moved = timeline.seek(mouse.0);
+                        }
+                    }
+                    // the slider keeps following the mouse until it's let go
+                    Some((px, py)) if timeline.contains((px as f64, py as f64)) => {
+                        moved = timeline.seek(mouse.0);
+                    }
+                    Some((px, py)) if camera.contains((px as f64, py as f64)) => {
+                        let (dx, dy) = (coordinates.0 - px, coordinates.1 - py);
+                        if dragged || dx.abs() + dy.abs() > 3.0 {
+                            camera.pan(dx as f64, dy as f64);
+                            drag = Some(coordinates);
+                            dragged = true;
+                            moved = true;
+                        }
+                    }
+                    Some(_) => {}
                 }
-                change = true;
-            } else if window.get_mouse_down(MouseButton::Right) {
-                if TEMP % 2 == 0 {
-                    img.attach(star, (x, y), Some(3));
-                    img.set_background(0xFF);
+            } else if let Some((px, py)) = drag.take() {
+                // only clicks on the map select, not those on the controls in the menu
+                if !dragged && camera.contains((px as f64, py as f64)) {
+                    selected = img.get_item((x, y)).and_then(|a| img.object(a)?.id);
+                    moved = true;
+                }
+            }
+
+            let now = if dragged || !tooltips {
+                None
+            } else {
+                img.get_item((x, y)).and_then(|a| img.object(a)?.id)
+            };
+            let now = now.map(|a| (a, (x, y)));
+            if now != hover {
+                // the tooltip follows the mouse, and is only written anew for another system
+                let same = matches!((now, hover), (Some((a, _)), Some((b, _))) if a == b);
+                let sprite = if same {
+                    None
                 } else {
-                    img.attach(smiley_tester, (x, y), Some(3));
-                    img.set_background(0xFF00);
+                    now.and_then(|(a, _)| galaxy.summary(a))
+                        .and_then(|x| img.tooltip(&x.join("\n")))
+                };
+                match (tip, sprite) {
+                    (Some(handle), Some(sprite)) => {
+                        img.replace(handle, &sprite);
+                        img.show(handle);
+                    }
+                    (Some(handle), None) if !same => {
+                        img.hide(handle);
+                    }
+                    (None, Some(sprite)) => {
+                        tip = Some(img.place(Object::new(sprite, (0, 0)), Some(10)))
+                    }
+                    _ => {}
+                }
+                if let (Some(handle), true) = (tip, now.is_some()) {
+                    img.move_to(handle, img.beside((x, y), handle));
                 }
-                TEMP += 1;
+                hover = now;
                 change = true;
             }
+
+            if input.scroll != 0.0 && camera.contains(mouse) {
+                camera.zoom_at(if input.scroll > 0.0 { 1.25 } else { 0.8 }, mouse);
+                moved = true;
+            }
         }
 
-        for keys in window.get_keys_pressed(KeyRepeat::No) {
-            for t in keys {
-                match t {
-                    Key::Escape => keep = false,
-                    _ => (),
+        let (vx, vy, vw, vh) = camera.view();
+        let middle = ((vx + vw / 2) as f64, (vy + vh / 2) as f64);
+        if !typing {
+            for key in input.keys {
+                let action = match bindings.action(key) {
+                    Some(a) => a,
+                    None => continue,
+                };
+                // a held key only does again what is meant to be done again
+                if !action.repeats() && !input.pressed.contains(&key) {
+                    continue;
+                }
+                notice.clear();
+                match action {
+                    Action::Quit => keep = false,
+                    Action::PanUp => camera.pan(0.0, 40.0),
+                    Action::PanLeft => camera.pan(40.0, 0.0),
+                    Action::PanDown => camera.pan(0.0, -40.0),
+                    Action::PanRight => camera.pan(-40.0, 0.0),
+                    Action::ZoomIn => camera.zoom_at(1.25, middle),
+                    Action::ZoomOut => camera.zoom_at(0.8, middle),
+                    Action::Home => camera = home,
+                    Action::PreviousDate => {
+                        timeline.step(-1);
+                    }
+                    Action::NextDate => {
+                        timeline.step(1);
+                    }
+                    Action::Play => timeline.play(),
+                    Action::Slower | Action::Faster => {
+                        speed = match action {
+                            Action::Slower => (speed / 2.0).max(1.0),
+                            _ => (speed * 2.0).min(36000.0),
+                        };
+                    }
+                    // frames are numbered from 0 again for every recording, overwriting older ones
+                    Action::Record => {
+                        recording = !recording;
+                        if recording {
+                            fs::create_dir_all("frames")?;
+                            frame = 0;
+                        }
+                    }
+                    // screenshots are numbered by date, and never overwritten
+                    Action::Screenshot => {
+                        fs::create_dir_all("screenshots")?;
+                        let date = galaxy.date_legible();
+                        let path = (0..)
+                            .map(|i| format!("screenshots/{}-{}.png", date, i))
+                            .find(|x| !std::path::Path::new(x).exists())
+                            .unwrap_or_default();
+                        img.save(&path)?;
+                        notice = format!("Saved {}", path);
+                    }
+                    Action::NextMode => style.mode = style.mode.next(),
+                    Action::PreviousMode => style.mode = style.mode.previous(),
+                    Action::Labels => {
+                        style.labels = style.labels.next();
+                        notice = String::from(style.labels.name());
+                    }
+                    Action::Heatmap => {
+                        style.heat = match style.heat {
+                            Some(_) => None,
+                            None => Some(saveread::Filter::All),
+                        }
+                    }
+                    // the layers are still drawn, just not shown
+                    Action::Territory => {
+                        territory = !territory;
+                        img.set_opacity(2, if territory { 1.0 } else { 0.0 });
+                    }
+                    Action::Hyperlanes => {
+                        hyperlanes = !hyperlanes;
+                        img.set_opacity(4, if hyperlanes { 1.0 } else { 0.0 });
+                    }
+                    Action::Tooltips => {
+                        tooltips = !tooltips;
+                        hover = None;
+                    }
+                    Action::Search if img.menu_width() >= 160 => ui.focus(search_area(&img)),
+                    // the help leaves searching out while there's no search field
+                    Action::Search => {}
+                    Action::Help => help = !help,
                 }
+                moved = true;
             }
         }
-
-        window.get_mouse_pos(MouseMode::Clamp).map(|_mouse| {
-            // println!("x {} y {}", mouse.0, mouse.1);
-        });
     }
     Ok(())
 }
 
+///how to run the program, shown when the arguments don't make sense.
+const USAGE: &str = "usage: test_crate [render <file.png|file.bmp|file.svg> [width height [date]]]";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    make_shit(900, 600)?;
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|x| x.as_str()) {
+        Some("render") => println!("wrote {}", render::cli(&args[2..])?),
+        Some(_) => return Err(USAGE.into()),
+        None => make_shit(900, 600)?,
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::{convert::TryInto, io::Read};
+
+    ///the colors of a 3 by 2 image, a different one in every pixel.
+    const PIXELS: [u32; 6] = [0x102030, 0x405060, 0x708090, 0xA0B0C0, 0xD0E0F0, 0x000000];
+
+    ///returns an image showing PIXELS over everything else, updated.
+    fn pixels() -> Img {
+        let mut img = Img::new(3, 2);
+        let content = PIXELS.iter().map(|x| OPAQUE | x).collect();
+        img.attach(FromImage::from_vec(content, 3, false), (0, 0), Some(2));
+        img.update();
+        img
+    }
+
+    #[test]
+    fn png_chunks_hold_the_image() {
+        let png = pixels().png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&rest[4..8 + length]);
+            assert_eq!(crc, hasher.finalize());
+            chunks.push((kind, data));
+            rest = &rest[12 + length..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|x| x.0).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        // 3 wide, 2 high, 8 bits per channel of rgb, no interlacing
+        assert_eq!(chunks[0].1, [0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        let mut rows = Vec::new();
+        ZlibDecoder::new(chunks[1].1)
+            .read_to_end(&mut rows)
+            .unwrap();
+        let mut expected = Vec::new();
+        for row in PIXELS.chunks(3) {
+            expected.push(0);
+            for x in row {
+                expected.extend_from_slice(&x.to_be_bytes()[1..]);
+            }
+        }
+        assert_eq!(rows, expected);
+        assert!(chunks[2].1.is_empty());
+    }
+
+    #[test]
+    fn bmp_holds_the_image() {
+        let bmp = pixels().bmp();
+        assert_eq!((bmp.get_width(), bmp.get_height()), (3, 2));
+        for (i, x) in PIXELS.iter().enumerate() {
+            let [_, r, g, b] = x.to_be_bytes();
+            let pixel = bmp.get_pixel(i as u32 % 3, i as u32 / 3);
+            assert_eq!((pixel.r, pixel.g, pixel.b), (r, g, b));
+        }
+    }
+
+    ///returns a square image with no menu, updated, and a white square on layer 2 at pos if
+    /// there is one, with its handle.
+    fn square(pos: Option<(usize, usize)>) -> (Img, Option<Handle>) {
+        let mut img = Img::new(64, 64);
+        let handle = pos.map(|x| {
+            let white = FromImage::from_vec(vec![OPAQUE | 0xFFFFFF; 16], 4, false);
+            img.attach(white, x, Some(2))
+        });
+        img.update();
+        (img, handle)
+    }
+
+    #[test]
+    fn removed_objects_are_erased() {
+        let (empty, _) = square(None);
+        let (mut img, handle) = square(Some((40, 40)));
+        assert_ne!(img.get_img(), empty.get_img());
+        img.remove(handle.unwrap());
+        img.update();
+        assert_eq!(img.get_img(), empty.get_img());
+    }
+
+    #[test]
+    fn moved_objects_leave_nothing_behind() {
+        let (there, _) = square(Some((40, 40)));
+        let (mut img, handle) = square(Some((2, 2)));
+        img.move_to(handle.unwrap(), (40, 40));
+        img.update();
+        assert_eq!(img.get_img(), there.get_img());
+    }
+
+    ///a selectable 4 by 4 white square.
+    fn white() -> Sprite {
+        Rc::new(FromImage::from_vec(vec![OPAQUE | 0xFFFFFF; 16], 4, true))
+    }
+
+    #[test]
+    fn objects_are_found_by_their_id_until_removed() {
+        let (mut img, _) = square(None);
+        let first = img.place(
+            Object {
+                id: Some(7),
+                ..Object::new(white(), (2, 2))
+            },
+            Some(5),
+        );
+        assert_eq!(img.find(5, 7), Some(first));
+        assert_eq!(img.find(4, 7), None);
+        let second = img.place(
+            Object {
+                id: Some(7),
+                ..Object::new(white(), (40, 40))
+            },
+            Some(5),
+        );
+        assert_eq!(img.find(5, 7), Some(second));
+        // removing the one placed before leaves the one found
+        img.remove(first);
+        assert_eq!(img.find(5, 7), Some(second));
+        img.remove(second);
+        assert_eq!(img.find(5, 7), None);
+        assert_eq!(img.remove(second), None);
+        assert!(!img.move_to(second, (0, 0)));
+    }
+
+    #[test]
+    fn hidden_objects_are_kept_but_not_shown() {
+        let (empty, _) = square(None);
+        let (mut img, _) = square(None);
+        let handle = img.place(Object::new(white(), (40, 40)), Some(5));
+        assert_eq!(img.get_item((41, 41)), Some(handle));
+        img.hide(handle);
+        img.update();
+        assert_eq!(img.get_item((41, 41)), None);
+        assert_eq!(img.get_img(), empty.get_img());
+        img.show(handle);
+        img.update();
+        assert_eq!(img.get_item((41, 41)), Some(handle));
+        assert_eq!(img.get_img()[41 * 64 + 41], 0xFFFFFF);
+    }
+
+    #[test]
+    fn replace_shows_another_sprite_in_place() {
+        let (mut img, _) = square(None);
+        let handle = img.place(Object::new(white(), (40, 40)), Some(5));
+        let black = Rc::new(FromImage::from_vec(vec![OPAQUE; 4], 2, true));
+        img.update();
+        img.replace(handle, &black);
+        img.update();
+        assert_eq!(img.get_img()[40 * 64 + 40], 0x000000);
+        assert_eq!(img.get_img()[43 * 64 + 43], 0x666666);
+        assert_eq!(img.get_item((43, 43)), None);
+        assert_eq!(img.obdim(handle), ((40, 40), (2, 2)));
+    }
+
+    #[test]
+    fn deselect_clears_the_selection_box() {
+        let (empty, _) = square(None);
+        let (mut img, _) = square(None);
+        img.select(((10, 10), (30, 20)));
+        img.update();
+        assert_eq!(img.get_img()[10 * 64 + 10], 0x000000);
+        img.deselect();
+        img.update();
+        assert_eq!(img.get_img(), empty.get_img());
+    }
+
+    #[test]
+    fn over_weighs_the_pixel_by_its_alpha() {
+        assert_eq!(over(0x102030, 0x00A0B0C0, 0xFF), 0x102030);
+        assert_eq!(over(0x102030, OPAQUE | 0xA0B0C0, 0xFF), 0xA0B0C0);
+        assert_eq!(over(0x000000, 0x80FFFFFF, 0xFF), 0x808080);
+        // the opacity of the layer counts as much as the alpha of the pixel
+        assert_eq!(over(0x000000, OPAQUE | 0xFFFFFF, 0x80), 0x808080);
+        assert_eq!(over(0x102030, OPAQUE | 0xA0B0C0, 0), 0x102030);
+    }
+
+    #[test]
+    fn tint_keeps_the_alpha() {
+        assert_eq!(tint(0x80FFFFFF, 0x336699), 0x80336699);
+        assert_eq!(tint(0x40808080, 0xFF8000), 0x40804000);
+        assert_eq!(tint(CLEAR, 0xFFFFFF), CLEAR);
+    }
+
+    #[test]
+    fn layer_opacity_lets_the_layers_below_through() {
+        let (empty, _) = square(None);
+        let (mut img, _) = square(Some((0, 0)));
+        img.set_opacity(2, 0.5);
+        img.update();
+        assert_eq!(img.get_img()[0], over(0x666666, OPAQUE | 0xFFFFFF, 0x80));
+        assert_eq!(img.get_img()[0], 0xB3B3B3);
+        img.set_opacity(2, 0.0);
+        img.update();
+        assert_eq!(img.get_img(), empty.get_img());
+    }
+
+    #[test]
+    fn clip_cuts_the_area_to_the_bounds() {
+        let bounds = (0, 0, 10, 10);
+        assert_eq!(clip((2, 3, 4, 5), bounds), Some((2, 3, 4, 5)));
+        assert_eq!(clip((-2, -3, 4, 5), bounds), Some((0, 0, 2, 2)));
+        assert_eq!(clip((8, 9, 4, 5), bounds), Some((8, 9, 2, 1)));
+        assert_eq!(clip((10, 0, 4, 5), bounds), None);
+        assert_eq!(clip((-4, 0, 4, 5), bounds), None);
+    }
+
+    #[test]
+    fn objects_only_show_inside_the_clip_of_their_layer() {
+        let (empty, _) = square(None);
+        let (mut img, _) = square(Some((6, 6)));
+        img.set_clip(2, Some((8, 8, 16, 16)));
+        img.update();
+        let pixel = |img: &Img, x: usize, y: usize| img.get_img()[y * 64 + x];
+        assert_eq!(pixel(&img, 7, 7), pixel(&empty, 7, 7));
+        assert_eq!(pixel(&img, 8, 8), 0xFFFFFF);
+        assert_eq!(pixel(&img, 9, 9), 0xFFFFFF);
+        // an object wholly outside the clip doesn't show, nor can it be found
+        let (mut img, handle) = square(Some((40, 40)));
+        img.set_clip(2, Some((8, 8, 16, 16)));
+        img.update();
+        assert_eq!(img.get_img(), empty.get_img());
+        assert_eq!(img.obdim(handle.unwrap()), ((0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn clips_past_the_edge_end_at_the_image() {
+        let (mut img, handle) = square(Some((62, 62)));
+        img.set_clip(2, Some((56, 56, 32, 32)));
+        assert_eq!(img.bounds(Some(2)), (56, 56, 8, 8));
+        img.update();
+        assert_eq!(img.get_img()[63 * 64 + 63], 0xFFFFFF);
+        assert_eq!(img.obdim(handle.unwrap()), ((62, 62), (2, 2)));
+        // a clip wholly outside the image leaves nothing of the layer
+        img.set_clip(2, Some((70, 70, 8, 8)));
+        assert_eq!(img.bounds(Some(2)), (0, 0, 0, 0));
+        assert_eq!(img.obdim(handle.unwrap()), ((0, 0), (0, 0)));
+    }
+
+    ///returns an uncompressed 32 bit bmp file of the 0xAARRGGBB pixels, kept from the bottom up
+    /// for a positive height as bmp files usually are.
+    fn bmp32(width: i32, height: i32, pixels: &[u32]) -> Vec<u8> {
+        let mut ret = Vec::new();
+        ret.extend_from_slice(b"BM");
+        ret.extend_from_slice(&(54 + 4 * pixels.len() as u32).to_le_bytes());
+        ret.extend_from_slice(&[0; 4]);
+        ret.extend_from_slice(&54u32.to_le_bytes());
+        ret.extend_from_slice(&40u32.to_le_bytes());
+        ret.extend_from_slice(&width.to_le_bytes());
+        ret.extend_from_slice(&height.to_le_bytes());
+        ret.extend_from_slice(&1u16.to_le_bytes());
+        ret.extend_from_slice(&32u16.to_le_bytes());
+        ret.extend_from_slice(&[0; 24]);
+        let rows: Vec<&[u32]> = pixels.chunks(width as usize).collect();
+        let rows: Vec<&[u32]> = if height < 0 {
+            rows
+        } else {
+            rows.into_iter().rev().collect()
+        };
+        for x in rows.concat() {
+            ret.extend_from_slice(&x.to_le_bytes());
+        }
+        ret
+    }
+
+    #[test]
+    fn read_argb_keeps_the_alpha() {
+        let pixels = [0x00102030, 0x80405060, 0xFF708090, 0x20A0B0C0];
+        assert_eq!(read_argb(&bmp32(2, 2, &pixels)), Some((pixels.to_vec(), 2)));
+        assert_eq!(
+            read_argb(&bmp32(2, -2, &pixels)),
+            Some((pixels.to_vec(), 2))
+        );
+    }
+
+    #[test]
+    fn read_argb_takes_files_without_alpha_as_opaque() {
+        let pixels = [0x102030, 0x405060];
+        let opaque = pixels.iter().map(|x| OPAQUE | x).collect();
+        assert_eq!(read_argb(&bmp32(2, 1, &pixels)), Some((opaque, 2)));
+    }
+
+    #[test]
+    fn read_argb_leaves_other_files() {
+        let mut data = bmp32(2, 1, &[0, 0]);
+        data[28] = 24;
+        assert_eq!(read_argb(&data), None);
+        assert_eq!(read_argb(b"GIF89a"), None);
+    }
+}
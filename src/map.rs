@@ -0,0 +1,1267 @@
+use crate::{
+    colors::ColorTable,
+    saveread::{Ethic, Filter, GalObject, Galaxy},
+    FromImage, Img, Object, Sprite, Stroke, CLEAR, OPAQUE,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+///what part of the galaxy is shown where: the galaxy coordinates at the middle of the view,
+/// the zoom in pixels per galaxy unit, and the view itself as (x, y, width, height) on screen.
+/// base is the zoom that fits the whole galaxy, which zooming is limited around.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    center: (f64, f64),
+    zoom: f64,
+    base: f64,
+    view: (usize, usize, usize, usize),
+}
+
+impl Camera {
+    ///returns a camera showing all of bounds ([min x, max x, min y, max y], as from Galaxy::maxc)
+    /// in the view, with a small margin.
+    pub fn fit(bounds: [f64; 4], view: (usize, usize, usize, usize)) -> Camera {
+        let zoom = 0.9
+            * (view.2 as f64 / (bounds[1] - bounds[0]))
+                .min(view.3 as f64 / (bounds[3] - bounds[2]));
+        Camera {
+            center: ((bounds[0] + bounds[1]) / 2.0, (bounds[2] + bounds[3]) / 2.0),
+            zoom,
+            base: zoom,
+            view,
+        }
+    }
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+    ///how far the camera is zoomed in from showing the whole galaxy.
+    pub fn magnification(&self) -> f64 {
+        self.zoom / self.base
+    }
+    pub fn view(&self) -> (usize, usize, usize, usize) {
+        self.view
+    }
+    ///moves the camera to a new view, keeping the same galaxy point in the middle and scaling
+    /// the zoom with the size of the view.
+    pub fn resize(&mut self, view: (usize, usize, usize, usize)) {
+        let ratio = (view.2.min(view.3) as f64 / self.view.2.min(self.view.3) as f64).max(0.01);
+        self.zoom *= ratio;
+        self.base *= ratio;
+        self.view = view;
+    }
+    ///turns galaxy coordinates into screen coordinates.
+    pub fn project(&self, inp: (f64, f64)) -> (f64, f64) {
+        (
+            (inp.0 - self.center.0) * self.zoom + self.view.0 as f64 + self.view.2 as f64 / 2.0,
+            (inp.1 - self.center.1) * self.zoom + self.view.1 as f64 + self.view.3 as f64 / 2.0,
+        )
+    }
+    ///turns screen coordinates into galaxy coordinates.
+    pub fn unproject(&self, inp: (f64, f64)) -> (f64, f64) {
+        (
+            (inp.0 - self.view.0 as f64 - self.view.2 as f64 / 2.0) / self.zoom + self.center.0,
+            (inp.1 - self.view.1 as f64 - self.view.3 as f64 / 2.0) / self.zoom + self.center.1,
+        )
+    }
+    ///whether the screen coordinates are inside the view.
+    pub fn contains(&self, inp: (f64, f64)) -> bool {
+        inp.0 >= self.view.0 as f64
+            && inp.0 < (self.view.0 + self.view.2) as f64
+            && inp.1 >= self.view.1 as f64
+            && inp.1 < (self.view.1 + self.view.3) as f64
+    }
+    ///zooms by factor, keeping the galaxy point under the screen coordinates at in place.
+    pub fn zoom_at(&mut self, factor: f64, at: (f64, f64)) {
+        let fixed = self.unproject(at);
+        self.zoom = (self.zoom * factor).clamp(self.base / 2.0, self.base * 40.0);
+        let moved = self.project(fixed);
+        self.pan(at.0 - moved.0, at.1 - moved.1);
+    }
+    ///moves the camera to the galaxy coordinates, zoomed in by magnification from showing the
+    /// whole galaxy, or further if it already is.
+    pub fn look_at(&mut self, inp: (f64, f64), magnification: f64) {
+        self.center = inp;
+        self.zoom = (self.base * magnification)
+            .max(self.zoom)
+            .clamp(self.base / 2.0, self.base * 40.0);
+    }
+    ///moves the galaxy by the given number of pixels on screen.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center.0 -= dx / self.zoom;
+        self.center.1 -= dy / self.zoom;
+    }
+}
+
+///which systems get their names written next to them on the map. Capitals are always named,
+/// other owned systems from twice the zoom that fits the galaxy, and the rest from four times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Labels {
+    All,
+    Owned,
+    Capitals,
+    Off,
+}
+
+impl Labels {
+    ///every option, in the order next goes through them.
+    pub const ALL: [Labels; 4] = [Labels::All, Labels::Owned, Labels::Capitals, Labels::Off];
+    ///returns how the option is called in the menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Labels::All => "All names",
+            Labels::Owned => "Owned names",
+            Labels::Capitals => "Capital names",
+            Labels::Off => "No names",
+        }
+    }
+    ///returns the next option, going from All to Off and around.
+    pub fn next(self) -> Labels {
+        match self {
+            Labels::All => Labels::Owned,
+            Labels::Owned => Labels::Capitals,
+            Labels::Capitals => Labels::Off,
+            Labels::Off => Labels::All,
+        }
+    }
+}
+
+///how the map is drawn: which systems are named, what the territory shows, and which pops
+/// the heatmap is made of, if there is one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Style {
+    pub labels: Labels,
+    pub mode: Mode,
+    pub heat: Option<Filter>,
+}
+
+///the labels of a legend, each with the color it explains. Where there are more colors than fit,
+/// the last label counts the rest and comes without a color.
+pub type Legend = Vec<(String, Option<u32>)>;
+
+///the region and color of systems by their ids, see territory.
+pub type Regions = HashMap<usize, (usize, u32)>;
+
+///what the territory around every system shows: the empire owning it, or something about
+/// the pops living there. Systems no one lives in are left empty in all but Owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Owner,
+    Pops,
+    Species,
+    Ethic,
+    Slaves,
+    Stability,
+}
+
+impl Mode {
+    ///every mode, in the order next goes through them.
+    pub const ALL: [Mode; 6] = [
+        Mode::Owner,
+        Mode::Pops,
+        Mode::Species,
+        Mode::Ethic,
+        Mode::Slaves,
+        Mode::Stability,
+    ];
+    ///returns how the mode is called in the menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Owner => "Owner",
+            Mode::Pops => "Pops",
+            Mode::Species => "Main species",
+            Mode::Ethic => "Main ethic",
+            Mode::Slaves => "Enslaved pops",
+            Mode::Stability => "Stability",
+        }
+    }
+    ///returns the next mode, going from Owner to Stability and around.
+    pub fn next(self) -> Mode {
+        let i = Mode::ALL.iter().position(|x| *x == self).unwrap_or(0);
+        Mode::ALL[(i + 1) % Mode::ALL.len()]
+    }
+    ///returns the mode before, going the other way around than next.
+    pub fn previous(self) -> Mode {
+        let i = Mode::ALL.iter().position(|x| *x == self).unwrap_or(0);
+        Mode::ALL[(i + Mode::ALL.len() - 1) % Mode::ALL.len()]
+    }
+    ///returns the region of every system, and the legend of their colors. Systems of the same
+    /// owner, species or ethic share a region, while those colored by an amount each get their own.
+    pub fn regions(self, galaxy: &Galaxy, table: &ColorTable) -> (Regions, Legend) {
+        match self {
+            Mode::Owner => {
+                let colors = empire_colors(galaxy, table);
+                let owners: HashMap<usize, usize> = galaxy
+                    .get_obj_iter()
+                    .filter_map(|x| Some((x.id(), x.owner()?)))
+                    .collect();
+                let names: HashMap<usize, &str> = galaxy
+                    .get_empire_iter()
+                    .map(|x| (x.id(), x.name()))
+                    .collect();
+                let systems = owners
+                    .iter()
+                    .filter_map(|(id, owner)| Some((*id, (*owner, *colors.get(owner)?))))
+                    .collect();
+                let legend = legend(&owners, |x| {
+                    let name = names.get(x).copied().unwrap_or("unknown");
+                    (
+                        name.to_string(),
+                        colors.get(x).copied().unwrap_or(BACKGROUND),
+                    )
+                });
+                (systems, legend)
+            }
+            Mode::Species => {
+                let species = galaxy.main_species();
+                // the most widespread species get the colors first, so they differ
+                let colors: HashMap<usize, u32> = rank(&species)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, x)| (*x, PALETTE[i % PALETTE.len()]))
+                    .collect();
+                let color = |x: &usize| colors.get(x).copied().unwrap_or(BACKGROUND);
+                let systems = species
+                    .iter()
+                    .map(|(id, x)| (*id, (*x, color(x))))
+                    .collect();
+                let legend = legend(&species, |x| {
+                    let name = galaxy.species_name(*x).unwrap_or("unknown");
+                    (name.to_string(), color(x))
+                });
+                (systems, legend)
+            }
+            Mode::Ethic => {
+                let ethics = galaxy.main_ethic();
+                // ethics have colors of their own, which tell them apart
+                let systems = ethics
+                    .iter()
+                    .map(|(id, x)| (*id, (ethic_color(x) as usize, ethic_color(x))))
+                    .collect();
+                let legend = legend(&ethics, |x| (x.to_string(), ethic_color(x)));
+                (systems, legend)
+            }
+            // pops are spread very unevenly, so the colors follow their square root to tell
+            // the many small colonies apart
+            Mode::Pops => {
+                let pops = galaxy.pop_counts();
+                let most = pops.values().copied().max().unwrap_or(1) as f64;
+                let systems = pops
+                    .iter()
+                    .map(|(id, x)| (*id, (*id, ramp(&HEAT, (*x as f64 / most).sqrt()))))
+                    .collect();
+                let legend = steps(&HEAT, |t| format!("{:.0} pops", most * t * t));
+                (systems, legend)
+            }
+            Mode::Slaves => {
+                let share = galaxy.slave_share();
+                let systems = share
+                    .iter()
+                    .map(|(id, x)| (*id, (*id, ramp(&HEAT, *x))))
+                    .collect();
+                let legend = steps(&HEAT, |t| format!("{:.0}% enslaved", t * 100.0));
+                (systems, legend)
+            }
+            Mode::Stability => {
+                let stability = galaxy.stability();
+                let systems = stability
+                    .iter()
+                    .map(|(id, x)| (*id, (*id, ramp(&STABILITY, x / 100.0))))
+                    .collect();
+                let legend = steps(&STABILITY, |t| format!("{:.0} stability", t * 100.0));
+                (systems, legend)
+            }
+        }
+    }
+}
+
+///the most entries a legend has.
+const LEGEND: usize = 10;
+
+///returns the legend of categories, e.g. owners, by the system ids having them: the label and
+/// color of each category from entry, the ones with the most systems first.
+fn legend<K: Ord>(systems: &HashMap<usize, K>, entry: impl Fn(&K) -> (String, u32)) -> Legend {
+    let ranked = rank(systems);
+    let mut ret: Legend = ranked
+        .iter()
+        .take(LEGEND)
+        .map(|x| {
+            let (label, color) = entry(x);
+            (label, Some(color))
+        })
+        .collect();
+    if ranked.len() > LEGEND {
+        ret.pop();
+        ret.push((format!("and {} more", ranked.len() - LEGEND + 1), None));
+    }
+    ret
+}
+
+///returns the categories the systems have, the ones with the most systems first.
+fn rank<K: Ord>(systems: &HashMap<usize, K>) -> Vec<&K> {
+    let mut count: BTreeMap<&K, usize> = BTreeMap::new();
+    for x in systems.values() {
+        *count.entry(x).or_default() += 1;
+    }
+    let mut count: Vec<(&K, usize)> = count.into_iter().collect();
+    count.sort_by_key(|(_, x)| Reverse(*x));
+    count.into_iter().map(|(x, _)| x).collect()
+}
+
+///returns the legend of a color ramp, with labels for five evenly spaced points along it.
+fn steps(stops: &[u32], label: impl Fn(f64) -> String) -> Legend {
+    (0..5)
+        .map(|x| x as f64 / 4.0)
+        .map(|t| (label(t), Some(ramp(stops, t))))
+        .collect()
+}
+
+///returns the color at t, from 0 to 1, along the colors evenly spaced from 0 to 1.
+fn ramp(stops: &[u32], t: f64) -> u32 {
+    let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (t.floor() as usize).min(stops.len() - 2);
+    mix(stops[i], stops[i + 1], t - i as f64)
+}
+
+///the ramp for amounts, from few in dark blue to many in yellow.
+const HEAT: [u32; 4] = [0x283C8C, 0x2A9DB4, 0x8CD25A, 0xF8E03C];
+///the ramp for stability, from red to green.
+const STABILITY: [u32; 3] = [0xC8352A, 0xE6C229, 0x2E9E4F];
+///the colors of species, given out by how widespread they are.
+const PALETTE: [u32; 10] = [
+    0xE6194B, 0x3CB44B, 0xFFE119, 0x4363D8, 0xF58231, 0x911EB4, 0x46F0F0, 0xF032E6, 0xBCF60C,
+    0xFABEBE,
+];
+
+///returns the color of the ethic, with opposite ethics far apart.
+fn ethic_color(inp: &Ethic) -> u32 {
+    match inp {
+        Ethic::Gestalt => 0x9A9A9A,
+        Ethic::Spiritualist => 0x8E44AD,
+        Ethic::Materialist => 0x2ECCFA,
+        Ethic::Egalitarian => 0xF1C40F,
+        Ethic::Authoritarian => 0xD35400,
+        Ethic::Pacifist => 0x27AE60,
+        Ethic::Militarist => 0xC0392B,
+        Ethic::Xenophile => 0xA3E048,
+        Ethic::Xenophobe => 0x7B4A2A,
+        Ethic::Non(_) => 0x444444,
+    }
+}
+
+///how many pixels an image kept by Cache may have, about 16 MB.
+const CACHED: usize = 1 << 22;
+
+///what an image kept by Cache shows: the territory colored by a mode, or the heatmap of a filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Shown {
+    Territory(Mode),
+    Heat(Filter),
+}
+
+///an image kept by Cache, drawn for the galaxy of the date at the zoom, with the galaxy point
+/// its top left corner shows and the legend that goes with it.
+#[derive(Debug, Clone)]
+struct Drawn {
+    date: usize,
+    shown: Shown,
+    zoom: f64,
+    corner: (f64, f64),
+    sprite: Sprite,
+    legend: Legend,
+}
+
+impl Drawn {
+    ///where the top left corner of the image goes on screen.
+    fn pos(&self, camera: &Camera) -> (i64, i64) {
+        let (x, y) = camera.project(self.corner);
+        (x.round() as i64, y.round() as i64)
+    }
+    ///the image as an object where the camera shows it.
+    fn object(&self, camera: &Camera) -> Object {
+        Object::new(self.sprite.clone(), self.pos(camera))
+    }
+    ///the part of the image in the view, see-through where it doesn't reach.
+    fn crop(&self, camera: &Camera) -> FromImage {
+        let (x, y, width, height) = camera.view();
+        let (px, py) = self.pos(camera);
+        let mut content = vec![CLEAR; width * height];
+        for i in 0..height {
+            for j in 0..width {
+                let (sx, sy) = (x as i64 + j as i64 - px, y as i64 + i as i64 - py);
+                if sx >= 0
+                    && sy >= 0
+                    && (sx as usize) < self.sprite.width()
+                    && (sy as usize) < self.sprite.len()
+                {
+                    content[i * width + j] = self.sprite[sy as usize][sx as usize];
+                }
+            }
+        }
+        FromImage::from_vec(content, width.max(1), false)
+    }
+    ///whether the image reaches over all of the view that anything of the galaxy can show in.
+    fn covers(&self, galaxy: &Galaxy, camera: &Camera) -> bool {
+        let (px, py) = self.pos(camera);
+        match needed(galaxy, camera) {
+            Some((x0, y0, x1, y1)) => {
+                px <= x0
+                    && py <= y0
+                    && px + self.sprite.width() as i64 >= x1
+                    && py + self.sprite.len() as i64 >= y1
+            }
+            None => true,
+        }
+    }
+}
+
+///how far past its outermost systems anything of the galaxy is drawn, in galaxy units.
+const MARGIN: f64 = 3.0 * BANDWIDTH;
+
+///the part (left, top, right, bottom) of the view that the galaxy and its margin show in,
+/// or None if they don't.
+fn needed(galaxy: &Galaxy, camera: &Camera) -> Option<(i64, i64, i64, i64)> {
+    let (x, y, width, height) = camera.view();
+    let bounds = galaxy.maxc();
+    let (left, top) = camera.project((bounds[0] - MARGIN, bounds[2] - MARGIN));
+    let (right, bottom) = camera.project((bounds[1] + MARGIN, bounds[3] + MARGIN));
+    let (x0, y0) = (
+        (left.ceil() as i64).max(x as i64),
+        (top.ceil() as i64).max(y as i64),
+    );
+    let (x1, y1) = (
+        (right.floor() as i64).min((x + width) as i64),
+        (bottom.floor() as i64).min((y + height) as i64),
+    );
+    if x0 < x1 && y0 < y1 {
+        Some((x0, y0, x1, y1))
+    } else {
+        None
+    }
+}
+
+///returns the galaxy point the top left corner of a new image shows, and its width and height:
+/// all of the galaxy if it fits in CACHED pixels at the zoom of the camera, or else the part in
+/// the view with half a view more on every side, or only the part in the view.
+fn area(galaxy: &Galaxy, camera: &Camera) -> ((f64, f64), usize, usize) {
+    let zoom = camera.zoom();
+    let bounds = galaxy.maxc();
+    let width = ((bounds[1] - bounds[0] + 2.0 * MARGIN) * zoom).ceil() as usize + 1;
+    let height = ((bounds[3] - bounds[2] + 2.0 * MARGIN) * zoom).ceil() as usize + 1;
+    if width * height <= CACHED {
+        return ((bounds[0] - MARGIN, bounds[2] - MARGIN), width, height);
+    }
+    let (vx, vy, vw, vh) = camera.view();
+    let (x0, y0, x1, y1) = needed(galaxy, camera).unwrap_or((
+        vx as i64,
+        vy as i64,
+        (vx + vw) as i64,
+        (vy + vh) as i64,
+    ));
+    let (width, height) = ((x1 - x0).max(1) as usize, (y1 - y0).max(1) as usize);
+    let (ex, ey) = if (width + vw) * (height + vh) <= CACHED {
+        (vw / 2, vh / 2)
+    } else {
+        (0, 0)
+    };
+    let corner = camera.unproject(((x0 - ex as i64) as f64, (y0 - ey as i64) as f64));
+    (corner, width + 2 * ex, height + 2 * ey)
+}
+
+///what draw keeps from one map to the next: the sprite stars are drawn with, and the territory
+/// and heatmaps it drew last, so going back to a date, or moving the camera without zooming,
+/// only has to place them anew.
+#[derive(Debug)]
+pub struct Cache {
+    star: Sprite,
+    drawn: Vec<Drawn>,
+}
+
+impl Cache {
+    pub fn new(star: Sprite) -> Cache {
+        Cache {
+            star,
+            drawn: Vec::new(),
+        }
+    }
+    ///how many images are kept, the one used longest ago going first.
+    const KEPT: usize = 6;
+    ///returns the image kept of what is shown for the galaxy at the zoom of the camera, if it
+    /// covers the view, or else the one returned by draw, which gets the galaxy point its top
+    /// left corner is to show and the width and height it's to have.
+    fn get(
+        &mut self,
+        galaxy: &Galaxy,
+        shown: Shown,
+        camera: &Camera,
+        draw: impl FnOnce((f64, f64), usize, usize) -> (FromImage, Legend),
+    ) -> Drawn {
+        let date = galaxy.date();
+        let zoom = camera.zoom();
+        let found = self.drawn.iter().position(|a| {
+            a.date == date && a.shown == shown && a.zoom == zoom && a.covers(galaxy, camera)
+        });
+        let drawn = match found {
+            Some(i) => self.drawn.remove(i),
+            None => {
+                self.drawn.retain(|a| a.date != date || a.shown != shown);
+                let (corner, width, height) = area(galaxy, camera);
+                let (sprite, legend) = draw(corner, width, height);
+                Drawn {
+                    date,
+                    shown,
+                    zoom,
+                    corner,
+                    sprite: Rc::new(sprite),
+                    legend,
+                }
+            }
+        };
+        if self.drawn.len() >= Cache::KEPT {
+            self.drawn.remove(0);
+        }
+        self.drawn.push(drawn.clone());
+        drawn
+    }
+}
+
+///replaces layers 2 to 5 of img with the territory, heatmap, hyperlanes and stars of the galaxy,
+/// as seen by the camera, and layer 9 with the names of the systems chosen by the labels of the
+/// style. The territory is colored by the mode of the style, whose legend is returned, and
+/// the heatmap of the style goes on a layer of its own over it.
+/// Every star is placed with the sprite of the cache and the id of its system, to be found by with Img::find.
+/// With next, the territory is blended towards that of the next galaxy by the factor, from 0
+/// to 1, and the hyperlanes and stars are taken from whichever of the two is closer.
+/// The territory and heatmap are drawn for more than the view and kept in cache, so moving the
+/// camera only places them anew, and only zooming or a date not drawn lately draws them again.
+pub fn draw(
+    img: &mut Img,
+    galaxy: &Galaxy,
+    next: Option<(&Galaxy, f64)>,
+    table: &ColorTable,
+    camera: &Camera,
+    style: &Style,
+    cache: &mut Cache,
+) -> Legend {
+    let (x, y, width, height) = camera.view();
+    let place = |a: &GalObject| {
+        let (px, py) = camera.project((a.gx(), a.gy()));
+        ((px - x as f64) as i64, (py - y as f64) as i64)
+    };
+    // the map is kept within the view, cutting off stars and lanes at its edge
+    for layer in [2, 3, 4, 5, 9] {
+        img.clear(layer);
+        img.set_clip(layer, Some(camera.view()));
+    }
+    let zoom = camera.zoom();
+    let radius = (RADIUS * zoom) as i64;
+    // where the systems go in an image whose top left corner shows the galaxy point corner
+    let inside = |corner: (f64, f64)| {
+        move |a: &GalObject| {
+            (
+                ((a.gx() - corner.0) * zoom).floor() as i64,
+                ((a.gy() - corner.1) * zoom).floor() as i64,
+            )
+        }
+    };
+    let mut lands = |galaxy: &Galaxy| {
+        cache.get(
+            galaxy,
+            Shown::Territory(style.mode),
+            camera,
+            |corner, w, h| {
+                let (regions, legend) = style.mode.regions(galaxy, table);
+                let map = territory(galaxy, &regions, inside(corner), w, h, radius);
+                (map, legend)
+            },
+        )
+    };
+    let from = lands(galaxy);
+    let (galaxy, legend) = match next {
+        Some((next, factor)) => {
+            let to = lands(next);
+            let map = blend(&from.crop(camera), &to.crop(camera), factor);
+            img.attach(map, (x, y), Some(2));
+            if factor < 0.5 {
+                (galaxy, from.legend)
+            } else {
+                (next, to.legend)
+            }
+        }
+        None => {
+            img.place(from.object(camera), Some(2));
+            (galaxy, from.legend)
+        }
+    };
+    if let Some(filter) = &style.heat {
+        let heat = cache.get(
+            galaxy,
+            Shown::Heat(filter.clone()),
+            camera,
+            |corner, w, h| {
+                let pops = galaxy.filtered_pops(filter);
+                let heat = heatmap(galaxy, &pops, inside(corner), w, h, BANDWIDTH * zoom);
+                (heat, Legend::new())
+            },
+        );
+        img.place(heat.object(camera), Some(3));
+    }
+    let hyperlanes = hyperlanes(galaxy, table, place, width, height);
+    img.attach(hyperlanes, (x, y), Some(4));
+    let star = cache.star.clone();
+    let (sw, sh) = (star.width() as i64, star.len() as i64);
+    let mut shown = Vec::new();
+    for a in galaxy.get_obj_iter() {
+        let (px, py) = place(a);
+        let (px, py) = (px - sw / 2, py - sh / 2);
+        if px + sw <= 0 || py + sh <= 0 || px >= width as i64 || py >= height as i64 {
+            continue;
+        }
+        let pos = (x as i64 + px, y as i64 + py);
+        let object = Object {
+            id: Some(a.id()),
+            name: Some(String::from(a.get_name())),
+            ..Object::new(star.clone(), pos)
+        };
+        img.place(object, Some(5));
+        shown.push(a);
+    }
+    name(
+        img,
+        galaxy,
+        &shown,
+        camera,
+        (sw as usize, sh as usize),
+        style.labels,
+    );
+    legend
+}
+
+///writes the names of the systems next to their stars on layer 9, trying right, left, over and
+/// under the star. Capitals go first and owned systems next, and a name that can't be placed
+/// without covering a star or another name, or leaving the view, is left out.
+fn name(
+    img: &mut Img,
+    galaxy: &Galaxy,
+    systems: &[&GalObject],
+    camera: &Camera,
+    star: (usize, usize),
+    labels: Labels,
+) {
+    let capitals = galaxy.capitals();
+    let magnification = camera.magnification();
+    let mut systems: Vec<(usize, &GalObject)> = systems
+        .iter()
+        .copied()
+        .map(|x| match (capitals.contains(&x.id()), x.owner()) {
+            (true, _) => (0, x),
+            (false, Some(_)) => (1, x),
+            (false, None) => (2, x),
+        })
+        .collect();
+    systems.retain(|(rank, _)| match (labels, rank) {
+        (Labels::Off, _) => false,
+        (Labels::Capitals, a) | (Labels::Owned, a) if *a > 1 => false,
+        (Labels::Capitals, a) if *a > 0 => false,
+        (_, 1) => magnification >= 2.0,
+        (_, 2) => magnification >= 4.0,
+        _ => true,
+    });
+    systems.sort_by_key(|(rank, _)| *rank);
+    let centers: Vec<(f64, f64)> = systems
+        .iter()
+        .map(|(_, x)| camera.project((x.gx(), x.gy())))
+        .collect();
+    let (vx, vy, vw, vh) = camera.view();
+    let (sw, sh) = (star.0 as i64, star.1 as i64);
+    // stars count as taken space, so names don't cover them
+    let mut taken: Vec<(i64, i64, i64, i64)> = centers
+        .iter()
+        .map(|(x, y)| (*x as i64 - sw / 2, *y as i64 - sh / 2, sw, sh))
+        .collect();
+    for ((rank, system), (x, y)) in systems.iter().zip(centers) {
+        let (width, height) = img.text_size(system.get_name(), 1);
+        let (width, height, x, y) = (width as i64, height as i64, x as i64, y as i64);
+        let places = [
+            (x + sw / 2 + 1, y - height / 2),
+            (x - sw / 2 - 1 - width, y - height / 2),
+            (x - width / 2, y - sh / 2 - height),
+            (x - width / 2, y + sh / 2),
+        ];
+        let place = places.iter().find(|(px, py)| {
+            *px >= vx as i64
+                && *py >= vy as i64
+                && px + width <= (vx + vw) as i64
+                && py + height <= (vy + vh) as i64
+                && !taken.iter().any(|(tx, ty, tw, th)| {
+                    *px < tx + tw && *tx < px + width && *py < ty + th && *ty < py + height
+                })
+        });
+        if let Some((px, py)) = place {
+            taken.push((*px, *py, width, height));
+            let color = if *rank == 0 { 0xFFFFFF } else { 0xD8D8D8 };
+            img.draw_text(
+                system.get_name(),
+                (*px as usize, *py as usize),
+                color,
+                1,
+                Some(9),
+            );
+        }
+    }
+}
+
+///how far territory reaches from an owned system, in galaxy units.
+pub const RADIUS: f64 = 25.0;
+
+///colors the space within radius pixels of every system in regions with the color of its region,
+/// e.g. that of the empire owning it. Every pixel goes to its nearest system, so neighbouring
+/// systems of one region merge, and a border is drawn in full color where it meets another
+/// region or empty space. place gives the position of a system within the returned image.
+pub fn territory(
+    galaxy: &Galaxy,
+    regions: &Regions,
+    place: impl Fn(&GalObject) -> (i64, i64),
+    width: usize,
+    height: usize,
+    radius: i64,
+) -> FromImage {
+    let mut dist = vec![i64::MAX; width * height];
+    let mut region = vec![None; width * height];
+    for obj in galaxy.get_obj_iter() {
+        let (cx, cy) = place(obj);
+        for y in (cy - radius).max(0)..(cy + radius + 1).min(height as i64) {
+            for x in (cx - radius).max(0)..(cx + radius + 1).min(width as i64) {
+                let d = (x - cx).pow(2) + (y - cy).pow(2);
+                let i = y as usize * width + x as usize;
+                if d <= radius * radius && d < dist[i] {
+                    dist[i] = d;
+                    region[i] = regions.get(&obj.id()).copied();
+                }
+            }
+        }
+    }
+    let mut content = vec![CLEAR; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let color = match region[i] {
+                Some((_, a)) => a,
+                None => continue,
+            };
+            let edge = (x > 0 && region[i - 1] != region[i])
+                || (x + 1 < width && region[i + 1] != region[i])
+                || (y > 0 && region[i - width] != region[i])
+                || (y + 1 < height && region[i + width] != region[i]);
+            content[i] = OPAQUE | if edge { color } else { (color >> 1) & 0x7F7F7F };
+        }
+    }
+    FromImage::from_vec(content, width, false)
+}
+
+///mixes two images of the same size, by factor from 0 (only from) to 1 (only to).
+/// A see-through pixel in one of them is mixed as the background of the map.
+pub fn blend(from: &FromImage, to: &FromImage, factor: f64) -> FromImage {
+    let content = from
+        .content
+        .iter()
+        .zip(to.content.iter())
+        .map(|(a, b)| match (*a == CLEAR, *b == CLEAR) {
+            (true, true) => CLEAR,
+            (true, false) => OPAQUE | mix(BACKGROUND, *b, factor),
+            (false, true) => OPAQUE | mix(*a, BACKGROUND, factor),
+            (false, false) => OPAQUE | mix(*a, *b, factor),
+        })
+        .collect();
+    FromImage::from_vec(content, from.width().max(1), false)
+}
+
+///mixes the channels of two 0xRRGGBB colors, by factor from 0 (only a) to 1 (only b).
+fn mix(a: u32, b: u32, factor: f64) -> u32 {
+    let channel = |shift: u32| {
+        let (a, b) = (((a >> shift) & 0xFF) as f64, ((b >> shift) & 0xFF) as f64);
+        ((a + (b - a) * factor).round() as u32) << shift
+    };
+    channel(16) + channel(8) + channel(0)
+}
+
+///how far the pops of a system spread on the heatmap, in galaxy units.
+pub const BANDWIDTH: f64 = 20.0;
+
+///returns the density of the pops, by system id, as an image to lay over the map: a kernel
+/// density estimate spreading the pops of every system around it by a gaussian bandwidth
+/// pixels wide. Denser places get warmer and less see-through colors, and places without pops
+/// stay clear. The densest system of the galaxy gets the hottest color, wherever the view is.
+/// place gives the position of a system within the returned image.
+pub fn heatmap(
+    galaxy: &Galaxy,
+    pops: &HashMap<usize, usize>,
+    place: impl Fn(&GalObject) -> (i64, i64),
+    width: usize,
+    height: usize,
+    bandwidth: f64,
+) -> FromImage {
+    let bandwidth = bandwidth.max(1.0);
+    let kernel = |d: f64| (-d * d / (2.0 * bandwidth * bandwidth)).exp();
+    let systems: Vec<((i64, i64), f64)> = galaxy
+        .get_obj_iter()
+        .filter_map(|x| Some((place(x), *pops.get(&x.id())? as f64)))
+        .filter(|(_, count)| *count > 0.0)
+        .collect();
+    let most = systems
+        .iter()
+        .map(|((ax, ay), _)| {
+            systems
+                .iter()
+                .map(|((bx, by), count)| {
+                    count * kernel((ax - bx) as f64) * kernel((ay - by) as f64)
+                })
+                .sum::<f64>()
+        })
+        .fold(0.0, f64::max);
+    let mut density = vec![0.0; width * height];
+    let reach = (3.0 * bandwidth).ceil() as i64;
+    for ((cx, cy), count) in systems.iter() {
+        let xs = (cx - reach).max(0)..(cx + reach + 1).min(width as i64);
+        let ys = (cy - reach).max(0)..(cy + reach + 1).min(height as i64);
+        if xs.is_empty() || ys.is_empty() {
+            continue;
+        }
+        // the gaussian is split into a factor for the column and one for the row
+        let columns: Vec<f64> = xs.clone().map(|x| kernel((x - cx) as f64)).collect();
+        for y in ys {
+            let row = count * kernel((y - cy) as f64);
+            let start = y as usize * width + xs.start as usize;
+            for (a, b) in density[start..].iter_mut().zip(columns.iter()) {
+                *a += row * b;
+            }
+        }
+    }
+    let content = density
+        .iter()
+        .map(|x| match x / most {
+            t if t.is_nan() || t < 0.02 => CLEAR,
+            t => {
+                let t = t.min(1.0);
+                let alpha = (60.0 + 170.0 * t.sqrt()) as u32;
+                alpha << 24 | ramp(&HEAT, t.sqrt())
+            }
+        })
+        .collect();
+    FromImage::from_vec(content, width.max(1), false)
+}
+
+///the color of the map behind the territory.
+pub const BACKGROUND: u32 = 0x666666;
+
+///draws every hyperlane once, under the place given by place for each end. Lanes inside one
+/// empire take its color, lanes between two empires are dashed, and the rest are thin and grey.
+pub fn hyperlanes(
+    galaxy: &Galaxy,
+    table: &ColorTable,
+    place: impl Fn(&GalObject) -> (i64, i64),
+    width: usize,
+    height: usize,
+) -> FromImage {
+    let colors = empire_colors(galaxy, table);
+    let systems: HashMap<usize, &GalObject> = galaxy.get_obj_iter().map(|x| (x.id(), x)).collect();
+    let mut ret = FromImage::blank(width, height);
+    for a in galaxy.get_obj_iter() {
+        for b in a.get_hyperlane_iter() {
+            let b = match systems.get(b) {
+                Some(b) if b.id() > a.id() => b,
+                _ => continue,
+            };
+            let (from, to) = (place(a), place(b));
+            ret.line(
+                (from.0 as f64, from.1 as f64),
+                (to.0 as f64, to.1 as f64),
+                lane_stroke(a, b, &colors),
+            );
+        }
+    }
+    ret
+}
+
+///how to draw the hyperlane between two systems, given the colors from empire_colors.
+pub fn lane_stroke(a: &GalObject, b: &GalObject, colors: &HashMap<usize, u32>) -> Stroke {
+    match (a.owner(), b.owner()) {
+        (Some(x), Some(y)) if x == y => Stroke {
+            color: colors.get(&x).copied().unwrap_or(LANE),
+            width: 2.0,
+            dash: None,
+        },
+        (Some(_), Some(_)) => Stroke {
+            color: LANE,
+            width: 1.0,
+            dash: Some((3.0, 3.0)),
+        },
+        _ => Stroke {
+            color: LANE,
+            width: 1.0,
+            dash: None,
+        },
+    }
+}
+
+///the color of hyperlanes that don't belong to a single empire.
+pub const LANE: u32 = 0x8C8C8C;
+
+///the map color of every empire that has one, by empire id.
+pub fn empire_colors(galaxy: &Galaxy, table: &ColorTable) -> HashMap<usize, u32> {
+    galaxy
+        .get_empire_iter()
+        .filter_map(|x| Some((x.id(), table.empire(x)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///two unowned systems, on opposite sides of the galaxy.
+    const SAVE: &str = "version=\"Shelley v2.5.1\"
+version_control_revision=1
+name=\"Test\"
+date=\"2210.10.19\"
+galactic_object={
+\t0={
+\t\tcoordinate={
+\t\t\tx=-50
+\t\t\ty=-40
+\t\t}
+\t\ttype=star
+\t\tname=\"Sol\"
+\t}
+\t1={
+\t\tcoordinate={
+\t\t\tx=50
+\t\t\ty=40
+\t\t}
+\t\ttype=star
+\t\tname=\"Alpha Centauri\"
+\t}
+}
+";
+
+    ///four systems in a row, 100 apart: Sol with 12 Blorgs and 3 humans, Vega and Deneb with
+    /// humans alone and Rigel with no one.
+    const POPS: &str = "version=\"Corvus v4.0.2\"
+version_control_revision=1
+name=\"Test\"
+date=\"2230.01.01\"
+species_db={
+\t16777216={
+\t\tname=\"Human\"
+\t\tplural=\"Humans\"
+\t\tadjective=\"Human\"
+\t}
+\t50331649={
+\t\tname=\"Blorg\"
+\t\tplural=\"Blorgs\"
+\t\tadjective=\"Blorgian\"
+\t}
+}
+pop_groups={
+\t3={
+\t\tkey={
+\t\t\tspecies=50331649
+\t\t\tcategory=\"worker\"
+\t\t\tethos={
+\t\t\t\tethic=\"ethic_materialist\"
+\t\t\t}
+\t\t}
+\t\tplanet=5
+\t\tsize=12
+\t}
+\t4={
+\t\tkey={
+\t\t\tspecies=16777216
+\t\t\tcategory=\"ruler\"
+\t\t}
+\t\tplanet=5
+\t\tsize=3
+\t}
+\t5={
+\t\tkey={
+\t\t\tspecies=16777216
+\t\t\tcategory=\"worker\"
+\t\t}
+\t\tplanet=6
+\t\tsize=5
+\t}
+\t6={
+\t\tkey={
+\t\t\tspecies=16777216
+\t\t\tcategory=\"worker\"
+\t\t}
+\t\tplanet=7
+\t\tsize=2
+\t}
+}
+galactic_object={
+\t0={
+\t\tcoordinate={
+\t\t\tx=0
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Sol\"
+\t\tplanet=5
+\t}
+\t1={
+\t\tcoordinate={
+\t\t\tx=100
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Vega\"
+\t\tplanet=6
+\t}
+\t2={
+\t\tcoordinate={
+\t\t\tx=200
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Deneb\"
+\t\tplanet=7
+\t}
+\t3={
+\t\tcoordinate={
+\t\t\tx=300
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Rigel\"
+\t}
+}
+planets={
+\tplanet={
+\t\t5={
+\t\t\tname=\"Earth\"
+\t\t\tplanet_class=\"pc_continental\"
+\t\t}
+\t\t6={
+\t\t\tname=\"Vega II\"
+\t\t\tplanet_class=\"pc_ocean\"
+\t\t}
+\t\t7={
+\t\t\tname=\"Deneb I\"
+\t\t\tplanet_class=\"pc_desert\"
+\t\t}
+\t}
+}
+";
+
+    #[test]
+    fn legend_puts_the_most_common_first() {
+        let systems: HashMap<usize, &str> =
+            [(1, "a"), (2, "b"), (3, "b"), (4, "c"), (5, "b"), (6, "c")]
+                .iter()
+                .copied()
+                .collect();
+        assert_eq!(rank(&systems), [&"b", &"c", &"a"]);
+        let legend = legend(&systems, |x| (x.to_uppercase(), x.len() as u32));
+        let labels: Vec<&str> = legend.iter().map(|x| x.0.as_str()).collect();
+        assert_eq!(labels, ["B", "C", "A"]);
+        assert!(legend.iter().all(|x| x.1 == Some(1)));
+    }
+
+    #[test]
+    fn legend_counts_what_doesnt_fit() {
+        // twelve categories, the first of three systems and the rest of one
+        let mut systems: HashMap<usize, usize> = (0..12).map(|x| (x, x)).collect();
+        systems.insert(12, 0);
+        systems.insert(13, 0);
+        let long = legend(&systems, |x| (x.to_string(), 0));
+        assert_eq!(long.len(), LEGEND);
+        assert_eq!(long[0], (String::from("0"), Some(0)));
+        assert_eq!(long[LEGEND - 1], (String::from("and 3 more"), None));
+        systems.retain(|x, _| *x < LEGEND);
+        let fitting = legend(&systems, |x| (x.to_string(), 0));
+        assert_eq!(fitting.len(), LEGEND);
+        assert!(fitting.iter().all(|x| x.1.is_some()));
+    }
+
+    #[test]
+    fn regions_group_systems_by_species_and_ethic() {
+        let galaxy = Galaxy::gamestate(POPS);
+        let table = ColorTable::new();
+        let (regions, legend) = Mode::Species.regions(&galaxy, &table);
+        // Vega and Deneb are both human, and humans live in the most systems
+        assert_eq!(regions[&1], regions[&2]);
+        assert_ne!(regions[&0], regions[&1]);
+        assert!(!regions.contains_key(&3));
+        let name = |x: usize| galaxy.species_name(regions[&x].0).unwrap();
+        assert_eq!((name(0), name(1)), ("Blorg", "Human"));
+        assert_eq!(
+            legend,
+            [
+                (String::from("Human"), Some(PALETTE[0])),
+                (String::from("Blorg"), Some(PALETTE[1])),
+            ]
+        );
+        let (regions, legend) = Mode::Ethic.regions(&galaxy, &table);
+        assert_eq!(regions[&0].1, ethic_color(&Ethic::Materialist));
+        assert_eq!(regions[&1].1, ethic_color(&Ethic::Gestalt));
+        assert_eq!(regions[&1], regions[&2]);
+        assert_eq!(legend.len(), 2);
+        assert_eq!(legend[0].1, Some(ethic_color(&Ethic::Gestalt)));
+    }
+
+    #[test]
+    fn regions_give_amounts_a_region_per_system() {
+        let galaxy = Galaxy::gamestate(POPS);
+        let (regions, legend) = Mode::Pops.regions(&galaxy, &ColorTable::new());
+        assert_eq!(regions.len(), 3);
+        assert!(regions.iter().all(|(id, x)| x.0 == *id));
+        // Sol has the most pops, so it gets the end of the ramp
+        assert_eq!(regions[&0].1, HEAT[3]);
+        assert_eq!(legend.len(), 5);
+        assert_eq!(legend[4], (String::from("15 pops"), Some(HEAT[3])));
+    }
+
+    #[test]
+    fn heatmap_is_hottest_where_most_pops_live() {
+        let galaxy = Galaxy::gamestate(POPS);
+        // the systems go at 10, 60, 110 and 160 along the middle row
+        let place = |a: &GalObject| ((a.gx() / 2.0) as i64 + 10, 10);
+        let pops = galaxy.filtered_pops(&Filter::All);
+        let heat = heatmap(&galaxy, &pops, place, 180, 21, 8.0);
+        let alpha = |heat: &FromImage, x: usize| heat[10][x] >> 24;
+        assert!(alpha(&heat, 10) >= 229);
+        assert!(alpha(&heat, 10) > alpha(&heat, 60));
+        assert!(alpha(&heat, 60) > alpha(&heat, 110));
+        // no one lives at Rigel, and it's too far from Deneb to get any of its heat
+        assert_eq!(heat[10][160], CLEAR);
+        assert_eq!(heat[0][135], CLEAR);
+        // Blorgs only live at Sol
+        let blorgs = galaxy.filtered_pops(&Filter::Species(String::from("Blorg")));
+        assert_eq!(blorgs.into_iter().collect::<Vec<_>>(), [(0, 12)]);
+        let heat = heatmap(
+            &galaxy,
+            &[(0, 12)].iter().copied().collect(),
+            place,
+            180,
+            21,
+            8.0,
+        );
+        assert!(alpha(&heat, 10) >= 229);
+        assert_eq!(heat[10][60], CLEAR);
+        let empty = heatmap(&galaxy, &HashMap::new(), place, 180, 21, 8.0);
+        assert!(empty.content.iter().all(|x| *x == CLEAR));
+    }
+
+    ///the sprite of the territory draw placed on layer 2, and where.
+    fn territory(img: &Img) -> (Sprite, (i64, i64)) {
+        let object = img.object(*img.layers[&2].iter().next().unwrap()).unwrap();
+        (object.sprite.clone(), object.pos)
+    }
+
+    ///a camera fitting bounds from -100 to 100 by -50 to 50 into a 200 by 100 view at (50, 20).
+    fn camera() -> Camera {
+        Camera::fit([-100.0, 100.0, -50.0, 50.0], (50, 20, 200, 100))
+    }
+
+    #[test]
+    fn fit_centers_the_bounds_in_the_view() {
+        let camera = camera();
+        assert_eq!(camera.project((0.0, 0.0)), (150.0, 70.0));
+        assert_eq!(camera.zoom(), 0.9);
+        assert_eq!(camera.magnification(), 1.0);
+    }
+
+    #[test]
+    fn unproject_undoes_project() {
+        let mut camera = camera();
+        camera.zoom_at(3.0, (80.0, 40.0));
+        camera.pan(-12.0, 7.5);
+        for point in [(0.0, 0.0), (-100.0, 50.0), (33.3, -12.5)] {
+            let (x, y) = camera.unproject(camera.project(point));
+            assert!((x - point.0).abs() < 1e-9 && (y - point.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn zoom_at_keeps_the_point_under_the_mouse() {
+        let mut camera = camera();
+        let under = camera.unproject((80.0, 40.0));
+        camera.zoom_at(2.0, (80.0, 40.0));
+        assert_eq!(camera.magnification(), 2.0);
+        let (x, y) = camera.project(under);
+        assert!((x - 80.0).abs() < 1e-9 && (y - 40.0).abs() < 1e-9);
+        // zooming is kept within half and forty times the zoom that fits
+        camera.zoom_at(1000.0, (80.0, 40.0));
+        assert_eq!(camera.magnification(), 40.0);
+        camera.zoom_at(0.0001, (80.0, 40.0));
+        assert_eq!(camera.magnification(), 0.5);
+    }
+
+    #[test]
+    fn pan_moves_the_galaxy_on_screen() {
+        let mut camera = camera();
+        camera.pan(30.0, -20.0);
+        assert_eq!(camera.project((0.0, 0.0)), (180.0, 50.0));
+        assert!(camera.contains((50.0, 20.0)));
+        assert!(!camera.contains((250.0, 70.0)));
+    }
+
+    #[test]
+    fn stars_are_found_where_the_camera_shows_them() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        let mut img = Img::new(200, 160);
+        let mut cache = Cache::new(img.sprite("./data/star.bmp", true).unwrap());
+        let mut camera = Camera::fit(galaxy.maxc(), (0, 0, 200, 160));
+        let style = Style {
+            labels: Labels::Off,
+            mode: Mode::Owner,
+            heat: None,
+        };
+        let table = ColorTable::new();
+        let sol = galaxy.get_obj_iter().find(|x| x.id() == 0).unwrap();
+        let at = |camera: &Camera| {
+            let (x, y) = camera.project((sol.gx(), sol.gy()));
+            (x as usize, y as usize)
+        };
+        let found = |img: &Img, pos| img.get_item(pos).and_then(|x| img.object(x)?.id);
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        let before = at(&camera);
+        assert_eq!(found(&img, before), Some(0));
+        camera.zoom_at(2.0, (0.0, 0.0));
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        assert_eq!(found(&img, before), None);
+        assert_eq!(found(&img, at(&camera)), Some(0));
+        // once out of the view it can't be found at all
+        camera.pan(-200.0, 0.0);
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        assert!(!camera.contains(camera.project((sol.gx(), sol.gy()))));
+        assert!((0..200).all(|x| (0..160).all(|y| found(&img, (x, y)).is_none())));
+    }
+
+    #[test]
+    fn moving_the_camera_keeps_the_territory() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        let mut img = Img::new(200, 160);
+        let mut cache = Cache::new(img.sprite("./data/star.bmp", true).unwrap());
+        let mut camera = Camera::fit(galaxy.maxc(), (0, 0, 200, 160));
+        let style = Style {
+            labels: Labels::All,
+            mode: Mode::Owner,
+            heat: None,
+        };
+        let table = ColorTable::new();
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        let (first, (x, y)) = territory(&img);
+        camera.pan(30.0, -20.0);
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        let (panned, pos) = territory(&img);
+        assert!(Rc::ptr_eq(&first, &panned));
+        assert_eq!(pos, (x + 30, y - 20));
+        camera.zoom_at(2.0, (100.0, 80.0));
+        draw(&mut img, &galaxy, None, &table, &camera, &style, &mut cache);
+        assert!(!Rc::ptr_eq(&first, &territory(&img).0));
+    }
+}
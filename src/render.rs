@@ -0,0 +1,148 @@
+use crate::{
+    colors::ColorTable,
+    map,
+    saveread::{Everything, Galaxy},
+    Img,
+};
+use std::{error::Error, fs};
+
+///returns the galaxy saved at the date, as the game writes it, or the last one.
+pub fn find<'a>(
+    everything: &'a Everything,
+    date: Option<&str>,
+) -> Result<&'a Galaxy, Box<dyn Error>> {
+    let dates = everything.dates();
+    let date = match date {
+        Some(a) => *dates
+            .iter()
+            .find(|x| everything[**x].date_legible() == a)
+            .ok_or_else(|| format!("No save from >{}<", a))?,
+        None => *dates.last().ok_or("No saves")?,
+    };
+    Ok(&everything[date])
+}
+
+///draws the map of the galaxy as an image of the size with no menu. Needs no window,
+/// so it works without a display.
+pub fn render(
+    galaxy: &Galaxy,
+    table: &ColorTable,
+    width: usize,
+    height: usize,
+) -> Result<Img, Box<dyn Error>> {
+    let mut img = Img::new(width, height);
+    img.clear(1);
+    let camera = map::Camera::fit(galaxy.maxc(), (0, 0, width, height));
+    let mut cache = map::Cache::new(img.sprite("./data/star.bmp", true)?);
+    map::draw(
+        &mut img,
+        galaxy,
+        None,
+        table,
+        &camera,
+        &map::Style {
+            labels: map::Labels::All,
+            mode: map::Mode::Owner,
+            heat: None,
+        },
+        &mut cache,
+    );
+    img.update();
+    Ok(img)
+}
+
+///renders a map from the command line arguments after "render": the file to write, then
+/// optionally the width and height, and the date of the save as the game writes it.
+/// Files ending with .svg get a vector map, see svg::galaxy. Returns the file written.
+pub fn cli(args: &[String]) -> Result<&str, Box<dyn Error>> {
+    let path = args.first().ok_or(crate::USAGE)?;
+    let size = |i: usize, default: usize| match args.get(i) {
+        Some(a) => a.parse::<usize>().map_err(|_| crate::USAGE),
+        None => Ok(default),
+    };
+    let (width, height) = (size(1, 900)?, size(2, 900)?);
+    if width == 0 || height == 0 {
+        return Err(crate::USAGE.into());
+    }
+    let everything = crate::saveread::reader()?;
+    let table = ColorTable::find()?;
+    let galaxy = find(&everything, args.get(3).map(|x| x.as_str()))?;
+    if path.to_lowercase().ends_with(".svg") {
+        fs::write(path, crate::svg::galaxy(galaxy, &table, width, height))?;
+    } else {
+        render(galaxy, &table, width, height)?.save(path)?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Camera, BACKGROUND};
+
+    ///two unowned systems joined by a hyperlane, on opposite sides of the galaxy.
+    const SAVE: &str = "version=\"Shelley v2.5.1\"
+version_control_revision=1
+name=\"Test\"
+date=\"2210.10.19\"
+galactic_object={
+\t0={
+\t\tcoordinate={
+\t\t\tx=-50
+\t\t\ty=-40
+\t\t}
+\t\ttype=star
+\t\tname=\"Sol\"
+\t\thyperlane={
+\t\t\t{
+\t\t\t\tto=1
+\t\t\t\tlength=50
+\t\t\t}
+\t\t}
+\t}
+\t1={
+\t\tcoordinate={
+\t\t\tx=50
+\t\t\ty=40
+\t\t}
+\t\ttype=star
+\t\tname=\"Alpha Centauri\"
+\t\thyperlane={
+\t\t\t{
+\t\t\t\tto=0
+\t\t\t\tlength=50
+\t\t\t}
+\t\t}
+\t}
+}
+";
+
+    #[test]
+    fn render_draws_stars_and_lanes_on_the_background() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        let img = render(&galaxy, &ColorTable::new(), 200, 160).unwrap();
+        assert_eq!((img.width(), img.height()), (200, 160));
+        assert_eq!(img.get_img().len(), 200 * 160);
+        let pixel = |(x, y): (f64, f64)| img.get_img()[y as usize * 200 + x as usize] & 0xFFFFFF;
+        let camera = Camera::fit(galaxy.maxc(), (0, 0, 200, 160));
+        let systems: Vec<(f64, f64)> = galaxy
+            .get_obj_iter()
+            .map(|a| camera.project((a.gx(), a.gy())))
+            .collect();
+        // stars are drawn over their systems, which is where the middle of the sprite goes
+        let star = Img::new(1, 1).sprite("./data/star.bmp", true).unwrap();
+        let middle = star[star.len() / 2][star.width() / 2] & 0xFFFFFF;
+        assert_ne!(middle, BACKGROUND);
+        for a in systems.iter() {
+            assert_eq!(pixel(*a), middle);
+        }
+        // the lane between them is grey, and off it and in the corners is the background
+        let ((ax, ay), (bx, by)) = (systems[0], systems[1]);
+        let lane = pixel(((ax + bx) / 2.0, (ay + by) / 2.0));
+        assert_ne!(lane, BACKGROUND);
+        assert!(lane >> 16 == lane & 0xFF && (lane >> 8) & 0xFF == lane & 0xFF);
+        assert_eq!(pixel((ax, by)), BACKGROUND);
+        assert_eq!(pixel((199.0, 159.0)), BACKGROUND);
+        assert_eq!(pixel((0.0, 0.0)), BACKGROUND);
+    }
+}
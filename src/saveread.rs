@@ -1,6 +1,6 @@
 use glob::glob;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
     fmt,
     fs::File,
@@ -9,7 +9,6 @@ use std::{
     ops,
     path::PathBuf,
 };
-use zip;
 
 pub fn reader() -> Result<Everything, Box<dyn Error>> {
     let mut world = read()?;
@@ -44,13 +43,11 @@ pub fn reader() -> Result<Everything, Box<dyn Error>> {
     for i in world.times.values_mut() {
         i.update_max([min_x, max_x, min_y, max_y]);
     }
-    // println!("{}, {}", min_x, max_x);
-    // println!("{}, {}", min_y, max_x);
     Ok(world)
 }
 
 ///enum of Ethic types for easier manipulation (than strings)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Ethic {
     Gestalt,
     Spiritualist,
@@ -112,6 +109,38 @@ impl fmt::Display for Ethic {
     }
 }
 
+///which pops to count, e.g. for the heatmap: all of them, those of a species by its name,
+/// those working jobs of a category, or those following an ethic.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Filter {
+    All,
+    Species(String),
+    Category(String),
+    Ethic(Ethic),
+}
+
+impl Filter {
+    fn matches(&self, pop: &Pop, galaxy: &Galaxy) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Species(name) => galaxy.species_name(pop.species) == Some(name.as_str()),
+            Filter::Category(category) => pop.category == *category,
+            Filter::Ethic(ethic) => pop.ethic == *ethic,
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::All => write!(f, "All pops"),
+            Filter::Species(name) => write!(f, "Species: {}", name),
+            Filter::Category(category) => write!(f, "Jobs: {}", category),
+            Filter::Ethic(ethic) => write!(f, "Ethic: {}", ethic),
+        }
+    }
+}
+
 ///currently only contains a vector of Galaxy, so member functions can be implemented.
 #[derive(Debug, Clone)]
 pub struct Everything {
@@ -137,6 +166,7 @@ impl Everything {
         Ok(ret)
     }
     fn save(&self, save: &mut File) -> Result<(), Box<dyn Error>> {
+        save.write_all(format!("version {}\n", CACHE_VERSION).as_bytes())?;
         for i in self.times.values() {
             i.save(save)?;
         }
@@ -145,6 +175,12 @@ impl Everything {
     pub fn get_obj_iter(&self) -> impl Iterator<Item = (&usize, &Galaxy)> {
         self.times.iter()
     }
+    ///returns the dates of every save, in order.
+    pub fn dates(&self) -> Vec<usize> {
+        let mut ret: Vec<usize> = self.get_obj_iter().map(|(a, _)| *a).collect();
+        ret.sort_unstable();
+        ret
+    }
 }
 
 impl ops::Index<usize> for Everything {
@@ -155,13 +191,15 @@ impl ops::Index<usize> for Everything {
 }
 
 ///the Galaxy object contains all info collected from a single save,
-/// in the form of a Date (usize), a vector of Empire, and a vector of Species.
+/// in the form of a Date (usize), a vector of Empire, a vector of Species,
+/// and every Planet by id, whether colonized or not.
 #[derive(Debug, Clone)]
 pub struct Galaxy {
     maxc: [f64; 4],
     date: usize,
     empires: Vec<Empire>,
     species: Vec<Species>,
+    planets: BTreeMap<usize, Planet>,
     obj: Vec<GalObject>,
 }
 
@@ -169,6 +207,10 @@ impl Galaxy {
     pub fn maxc(&self) -> [f64; 4] {
         self.maxc
     }
+    ///returns the date in days since 2200.01.01.
+    pub fn date(&self) -> usize {
+        self.date
+    }
     fn update_max(&mut self, inp: [i64; 4]) {
         self.maxc = [
             inp[0] as f64 / 1000f64,
@@ -180,13 +222,26 @@ impl Galaxy {
     pub fn get_obj_iter(&self) -> impl Iterator<Item = &GalObject> {
         self.obj.iter()
     }
-    ///returns an empty Galaxy-object; date = 0, and empires+species+obj are empty vectors.
+    pub fn get_empire_iter(&self) -> impl Iterator<Item = &Empire> {
+        self.empires.iter()
+    }
+    ///returns the ids of the systems with the capital of an empire.
+    pub fn capitals(&self) -> HashSet<usize> {
+        let planets: HashSet<usize> = self.empires.iter().filter_map(|x| x.capital).collect();
+        self.obj
+            .iter()
+            .filter(|x| x.planets.iter().any(|y| planets.contains(y)))
+            .map(|x| x.id)
+            .collect()
+    }
+    ///returns an empty Galaxy-object; date = 0, and empires+species+planets+obj are empty.
     fn new() -> Galaxy {
         Galaxy {
             maxc: [0.0; 4],
             date: 0,
             empires: Vec::new(),
             species: Vec::new(),
+            planets: BTreeMap::new(),
             obj: Vec::new(),
         }
     }
@@ -210,6 +265,11 @@ impl Galaxy {
                         ret.empires.push(emp);
                     }
                 }
+                Some("\tplanets {") => {
+                    while let Some(plan) = Planet::read(it)? {
+                        ret.planets.insert(plan.id, plan);
+                    }
+                }
                 Some("\tobjects {") => {
                     while let Some(obj) = GalObject::read(it)? {
                         ret.obj.push(obj);
@@ -281,20 +341,273 @@ impl Galaxy {
             self.species.push(next);
         }
     }
-    fn date_legible(&self) -> String {
+    ///takes a mutable reference to an iterator over the file, and reads the area between
+    /// "species_db={ ... }" of newer saves into the species-field of a Galaxy object,
+    /// returning where every species went in it by its id. Names given as localisation keys,
+    /// as in name={ key="SPEC_Human" }, are kept as the key.
+    fn get_species_db<'a>(
+        &mut self,
+        inp: &mut impl Iterator<Item = &'a str>,
+    ) -> Result<HashMap<usize, usize>, String> {
+        let mut ret = HashMap::new();
+        while let Some(line1) = inp.next() {
+            if line1 == "}" {
+                break;
+            }
+            let id = match line1.get(1..(line1.chars().count() - 2)) {
+                Some(a) => parser(a)?,
+                None => return Err(format!("Couldn't get species id from: >{}<", line1)),
+            };
+            let mut next = Species::new();
+            // the block of the entry the line is in, for names given as keys
+            let mut block = "";
+            for line2 in inp.by_ref() {
+                if line2 == "\t}" {
+                    break;
+                }
+                let (key, value) = match line2.trim_start_matches('\t').split_once('=') {
+                    Some((a, b)) => (a, b.trim_matches('"')),
+                    None => {
+                        if line2.trim() == "}" {
+                            block = "";
+                        }
+                        continue;
+                    }
+                };
+                match (key, block) {
+                    (_, _) if value == "{" => block = key,
+                    ("name", "") | ("key", "name") => next.name = value.to_owned(),
+                    ("plural", "") | ("key", "plural") => next.plural = value.to_owned(),
+                    ("adjective", "") | ("key", "adjective") => next.adjective = value.to_owned(),
+                    ("portrait", "") => next.portrait = value.to_owned(),
+                    ("trait", "traits") => next.traits.push(value.to_owned()),
+                    _ => {}
+                }
+            }
+            ret.insert(id, self.species.len());
+            self.species.push(next);
+        }
+        Ok(ret)
+    }
+    ///returns lines describing the system with the id: its name, star type, owner, planets and
+    /// the species living there. Habitability is given for the species with the most pops in
+    /// the system. Returns None if there is no such system.
+    pub fn details(&self, id: usize) -> Option<Vec<String>> {
+        let system = self.obj.iter().find(|x| x.id == id)?;
+        let planets: Vec<&Planet> = system
+            .planets
+            .iter()
+            .filter_map(|x| self.planets.get(x))
+            .collect();
+        let mut species: BTreeMap<usize, usize> = BTreeMap::new();
+        for pop in planets.iter().flat_map(|x| x.population.iter()) {
+            *species.entry(pop.species).or_default() += pop.size;
+        }
+        let main = species
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .and_then(|(x, _)| self.species.get(*x));
+        let mut ret = vec![
+            system.name.clone(),
+            format!("Star: {}", system.typ),
+            self.owner_line(system),
+            format!("Planets: {}", planets.len()),
+        ];
+        for planet in planets {
+            let mut line = format!(
+                " {}: {} ({})",
+                planet.name,
+                planet.typ.trim_start_matches("pc_"),
+                planet.size
+            );
+            if planet.pop_count() > 0 {
+                line.push_str(&format!(", {} pops", planet.pop_count()));
+            }
+            if let Some(a) = main {
+                match planet.habitability(a) {
+                    x if x > 0.0 => line.push_str(&format!(", {:.0}% hab.", x * 100.0)),
+                    _ => {}
+                }
+            }
+            ret.push(line);
+        }
+        if !species.is_empty() {
+            ret.push(String::from("Species:"));
+        }
+        for (i, count) in species {
+            let name = self.species.get(i).map_or("unknown", |x| x.name.as_str());
+            ret.push(format!(" {}: {}", name, count));
+        }
+        Some(ret)
+    }
+    ///returns the name, owner and number of pops of the system with the id, as lines for a tooltip.
+    pub fn summary(&self, id: usize) -> Option<Vec<String>> {
+        let system = self.obj.iter().find(|x| x.id == id)?;
+        let pops: usize = system
+            .planets
+            .iter()
+            .filter_map(|x| self.planets.get(x))
+            .map(|x| x.pop_count())
+            .sum();
+        Some(vec![
+            system.name.clone(),
+            self.owner_line(system),
+            format!("Pops: {}", pops),
+        ])
+    }
+    ///returns the number of pops in every system anyone lives in, by system id.
+    pub fn pop_counts(&self) -> HashMap<usize, usize> {
+        self.count_pops(|_| ())
+            .into_iter()
+            .map(|(id, x)| (id, x.values().sum()))
+            .collect()
+    }
+    ///returns the index of the species with the most pops in every system anyone lives in.
+    pub fn main_species(&self) -> HashMap<usize, usize> {
+        self.count_pops(|x| x.species)
+            .into_iter()
+            .filter_map(|(id, x)| Some((id, most(x)?)))
+            .collect()
+    }
+    ///returns the ethic most pops follow in every system anyone lives in.
+    pub fn main_ethic(&self) -> HashMap<usize, Ethic> {
+        self.count_pops(|x| x.ethic.clone())
+            .into_iter()
+            .filter_map(|(id, x)| Some((id, most(x)?)))
+            .collect()
+    }
+    ///returns the share of enslaved pops, from 0 to 1, in every system anyone lives in.
+    pub fn slave_share(&self) -> HashMap<usize, f64> {
+        self.count_pops(|x| x.slave)
+            .into_iter()
+            .map(|(id, x)| {
+                let all: usize = x.values().sum();
+                let slaves = x.get(&true).copied().unwrap_or(0);
+                (id, slaves as f64 / all as f64)
+            })
+            .collect()
+    }
+    ///returns the stability of every system anyone lives in, from 0 to 100, as the average
+    /// of its planets weighed by their pops.
+    pub fn stability(&self) -> HashMap<usize, f64> {
+        let mut ret = HashMap::new();
+        for system in self.obj.iter() {
+            let planets = system.planets.iter().filter_map(|x| self.planets.get(x));
+            let (sum, pops) = planets.fold((0.0, 0), |(sum, pops), x| {
+                (
+                    sum + x.stability * x.pop_count() as f64,
+                    pops + x.pop_count(),
+                )
+            });
+            if pops > 0 {
+                ret.insert(system.id, sum / pops as f64);
+            }
+        }
+        ret
+    }
+    ///returns the number of pops matching the filter in every system where any of them live.
+    pub fn filtered_pops(&self, filter: &Filter) -> HashMap<usize, usize> {
+        self.count_pops(|x| filter.matches(x, self))
+            .into_iter()
+            .filter_map(|(id, x)| Some((id, *x.get(&true)?)))
+            .collect()
+    }
+    ///returns every filter matching any pop: all pops, then every species, job category
+    /// and ethic that pops have.
+    pub fn filters(&self) -> Vec<Filter> {
+        let mut ret = BTreeSet::new();
+        for pop in self.planets.values().flat_map(|x| x.population.iter()) {
+            if let Some(a) = self.species_name(pop.species) {
+                ret.insert(Filter::Species(a.to_owned()));
+            }
+            if !pop.category.is_empty() {
+                ret.insert(Filter::Category(pop.category.clone()));
+            }
+            ret.insert(Filter::Ethic(pop.ethic.clone()));
+        }
+        ret.insert(Filter::All);
+        ret.into_iter().collect()
+    }
+    ///returns everything that can be searched for, as a label saying what it is with the id of
+    /// the system to show for it: every system and planet, every empire by its name and its
+    /// adjective, shown at its capital, and every species, shown where most of its pops live.
+    pub fn targets(&self) -> Vec<(String, usize)> {
+        let mut ret = Vec::new();
+        let mut planets = HashMap::new();
+        for system in self.obj.iter() {
+            ret.push((format!("System: {}", system.name), system.id));
+            for planet in system.planets.iter() {
+                planets.insert(*planet, system.id);
+                if let Some(a) = self.planets.get(planet) {
+                    ret.push((format!("Planet: {}", a.name), system.id));
+                }
+            }
+        }
+        for empire in self.empires.iter() {
+            let home = empire.capital.and_then(|x| planets.get(&x)).copied();
+            let home = home.or_else(|| {
+                let owned = self.obj.iter().find(|x| x.owner == Some(empire.id));
+                owned.map(|x| x.id)
+            });
+            if let Some(a) = home {
+                ret.push((format!("Empire: {}", empire.name), a));
+                if !empire.adjective.is_empty() && empire.adjective != empire.name {
+                    ret.push((format!("Empire: {} ({})", empire.adjective, empire.name), a));
+                }
+            }
+        }
+        let mut species: HashMap<usize, (usize, usize)> = HashMap::new();
+        for (system, pops) in self.count_pops(|x| x.species) {
+            for (i, count) in pops {
+                let most = species.entry(i).or_insert((system, count));
+                // ties go to the lower id, so the same system is shown every time
+                if count > most.1 || (count == most.1 && system < most.0) {
+                    *most = (system, count);
+                }
+            }
+        }
+        for (i, (system, _)) in species {
+            if let Some(a) = self.species_name(i) {
+                ret.push((format!("Species: {}", a), system));
+            }
+        }
+        ret
+    }
+    ///returns the name of the species with the index.
+    pub fn species_name(&self, i: usize) -> Option<&str> {
+        self.species.get(i).map(|x| x.name.as_str())
+    }
+    ///counts the pops of every system anyone lives in by key, e.g. by species.
+    fn count_pops<K: Ord>(&self, key: impl Fn(&Pop) -> K) -> HashMap<usize, BTreeMap<K, usize>> {
+        let mut ret: HashMap<usize, BTreeMap<K, usize>> = HashMap::new();
+        for system in self.obj.iter() {
+            let planets = system.planets.iter().filter_map(|x| self.planets.get(x));
+            for pop in planets.flat_map(|x| x.population.iter()) {
+                if pop.size > 0 {
+                    *ret.entry(system.id)
+                        .or_default()
+                        .entry(key(pop))
+                        .or_default() += pop.size;
+                }
+            }
+        }
+        ret
+    }
+    fn owner_line(&self, system: &GalObject) -> String {
+        match system
+            .owner
+            .and_then(|x| self.empires.iter().find(|y| y.id == x))
+        {
+            Some(a) => format!("Owner: {}", a.name),
+            None => String::from("Owner: none"),
+        }
+    }
+    ///returns the date as the game writes it, e.g. 2200.01.01.
+    pub fn date_legible(&self) -> String {
         let year = self.date / 360;
         let month = (self.date / 30) - (12 * year);
         let day = self.date - (month * 30) - (year * 360);
-        match day % 10 {
-            0 => match month % 10 {
-                0 => format!("{}.0{}.0{}", year + 2200, month + 1, day + 1),
-                _ => format!("{}.{}.0{}", year + 2200, month + 1, day + 1),
-            },
-            _ => match month % 10 {
-                0 => format!("{}.0{}.{}", year + 2200, month + 1, day + 1),
-                _ => format!("{}.{}.{}", year + 2200, month + 1, day + 1),
-            },
-        }
+        format!("{}.{:02}.{:02}", year + 2200, month + 1, day + 1)
     }
     fn save(&self, save: &mut File) -> Result<(), Box<dyn Error>> {
         save.write_all(format!("{} {{\n\tspecies {{\n", self.date_legible()).as_bytes())?;
@@ -305,6 +618,10 @@ impl Galaxy {
         for i in self.empires.iter() {
             i.save(save)?;
         }
+        save.write_all(b"\t}\n\tplanets {\n")?;
+        for i in self.planets.values() {
+            i.save(save)?;
+        }
         save.write_all(b"\t}\n\tobjects {\n")?;
         for i in self.obj.iter() {
             i.save(save)?;
@@ -314,48 +631,58 @@ impl Galaxy {
     }
 }
 
-///An empire, containing all planets it controls. Also ethics if applicable.
+///An empire, containing the ids of all planets it controls. Also ethics if applicable.
 #[derive(Clone)]
-struct Empire {
+pub struct Empire {
     id: usize,
     name: String,
     adjective: String,
-    planets: Vec<Planet>,
+    planets: Vec<usize>,
     ethics: Option<[Ethic; 3]>,
     color: [Option<String>; 4],
+    capital: Option<usize>,
 }
 
 impl Empire {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    ///the name of the empire's map color, which is the first of its flag colors.
+    pub fn map_color(&self) -> Option<&str> {
+        self.color[0].as_deref()
+    }
     fn newe() -> Empire {
         Empire {
-            id: std::usize::MAX,
+            id: usize::MAX,
             name: String::new(),
             adjective: String::new(),
             planets: Vec::new(),
             ethics: None,
             color: [None, None, None, None],
+            capital: None,
         }
     }
     ///Returns an Empire object with no planets and no ethics.
-    fn new<'a>(
-        it: &mut impl Iterator<Item = &'a str>,
-        plan: &mut HashMap<usize, Planet>,
-    ) -> Result<Option<Empire>, String> {
+    fn new<'a>(it: &mut impl Iterator<Item = &'a str>) -> Result<Option<Empire>, String> {
         let id = match it.next() {
             Some("}") => return Ok(None),
             Some(line) => match line.get(1..(line.chars().count() - 2)) {
                 Some(a) => parser(a)?,
                 None => return Err(format!("Couldn't parse Emprie ID: >{}<", line)),
             },
-            None => return Err(format!("Couldn't parse Empire ID; end of iterator")),
+            None => return Err("Couldn't parse Empire ID; end of iterator".to_string()),
         };
         let mut ret = Empire {
-            id: id,
+            id,
             name: String::new(),
             adjective: String::new(),
             planets: Vec::new(),
             ethics: None,
             color: [None, None, None, None],
+            capital: None,
         };
         while let Some(line) = it.next() {
             if line == "\t}" {
@@ -367,14 +694,14 @@ impl Empire {
                         let line = match it.next() {
                             Some(a) => a,
                             None => {
-                                return Err(format!(
-                                    "Cannot iterate when collecting colors for empires"
-                                ))
+                                return Err(
+                                    "Cannot iterate when collecting colors for empires".to_string()
+                                )
                             }
                         };
                         ret.color[i] = match line.get(5..(line.chars().count() - 1)) {
                             Some(a) => str_to_color(a),
-                            None => return Err(format!("Cannot index into color for empires")),
+                            None => return Err("Cannot index into color for empires".to_string()),
                         }
                     }
                 }
@@ -386,6 +713,17 @@ impl Empire {
                         }
                     }
                 }
+                Some("capita") => {
+                    ret.capital = match line.get(10..) {
+                        Some(a) => Some(parser(a)?),
+                        None => {
+                            return Err(format!(
+                                "Cannot get capital from line >{}< for empires",
+                                line
+                            ))
+                        }
+                    }
+                }
                 Some("adject") => {
                     ret.adjective = match line.get(13..(line.chars().count() - 1)) {
                         Some(a) => a.to_owned(),
@@ -400,7 +738,7 @@ impl Empire {
                 Some("ethos=") => {
                     let mut i = 0;
                     let mut temp = [Ethic::Gestalt, Ethic::Gestalt, Ethic::Gestalt];
-                    while let Some(line2) = it.next() {
+                    for line2 in it.by_ref() {
                         if line2 == "\t\t}" {
                             temp[2] = temp[1].clone();
                             break;
@@ -446,17 +784,14 @@ impl Empire {
                         match line2.get(3..(line2.chars().count() - 1)) {
                             Some(a) => {
                                 for i in a.split(' ') {
-                                    ret.planets.push(match plan.remove(&parser(i)?) {
-                                        Some(a) => a,
-                                        None => continue,
-                                    })
+                                    ret.planets.push(parser(i)?);
                                 }
                             }
                             None => return Err(format!("Found no owner planets: >{}<", line2)),
                         };
                     }
                     None => {
-                        return Err(format!("Couldn't get owned planets"));
+                        return Err("Couldn't get owned planets".to_string());
                     }
                 },
                 _ => {}
@@ -493,15 +828,19 @@ impl Empire {
             Some("\t\t\t\tGestalt") => Some([Ethic::Gestalt, Ethic::Gestalt, Ethic::Gestalt]),
             Some(a) => Some([
                 Ethic::read_to_ethic(a.get(4..).unwrap()),
-                Ethic::read_to_ethic(&it.next().unwrap().get(4..).unwrap()),
-                Ethic::read_to_ethic(&it.next().unwrap().get(4..).unwrap()),
+                Ethic::read_to_ethic(it.next().unwrap().get(4..).unwrap()),
+                Ethic::read_to_ethic(it.next().unwrap().get(4..).unwrap()),
             ]),
             None => panic!("Despacito"),
         };
         it.next();
         it.next();
-        while let Some(emp) = Planet::read(it)? {
-            ret.planets.push(emp);
+        loop {
+            match it.next().unwrap().get(..) {
+                Some("\t\t\t}") => break,
+                Some(a) => ret.planets.push(parser(a.get(4..).unwrap())?),
+                None => panic!("Des?pacito"),
+            }
         }
         it.next();
         for i in 0..4 {
@@ -512,6 +851,11 @@ impl Empire {
             }
         }
         it.next();
+        ret.capital = match it.next().unwrap().get(11..) {
+            Some("None") => None,
+            Some(a) => Some(parser(a)?),
+            None => panic!("Des?pa?ci?to"),
+        };
         it.next();
         Ok(Some(ret))
     }
@@ -537,7 +881,7 @@ impl Empire {
         }
         save.write_all(b"\t\t\t}\n\t\t\tplanets {\n")?;
         for i in self.planets.iter() {
-            i.save(save)?;
+            save.write_all(format!("\t\t\t\t{}\n", i).as_bytes())?;
         }
         save.write_all(b"\t\t\t}\n\t\t\tcolors {\n")?;
         for i in self.color.iter() {
@@ -546,46 +890,53 @@ impl Empire {
                 None => save.write_all(b"\t\t\t\tNone\n")?,
             }
         }
-        save.write_all(b"\t\t\t}\n\t\t}\n")?;
+        match self.capital {
+            Some(a) => {
+                save.write_all(format!("\t\t\t}}\n\t\t\tcapital {}\n\t\t}}\n", a).as_bytes())?
+            }
+            None => save.write_all(b"\t\t\t}\n\t\t\tcapital None\n\t\t}\n")?,
+        }
         Ok(())
     }
 }
 
-///a planet; id in usize, name in string, type in string, size in usize, population in vec of Pop
+///a planet; id in usize, name in string, type in string, size in usize, orbit in i64
+/// (multiplied by 1000, like coordinates), deposits in vec of string, population in vec of Pop.
+/// colonized is true for planets with an owner, so empty worlds can be told apart from colonies.
+/// Saves don't keep habitability, it depends on who would live there: Planet::habitability
+/// derives it from the class and the climate preference of a species.
+/// stability goes from 0 to 100, and is 0 for planets no one lives on.
 #[derive(Clone)]
 struct Planet {
     id: usize,
     name: String,
     typ: String,
     size: usize,
+    orbit: i64,
+    colonized: bool,
+    stability: f64,
+    deposits: Vec<String>,
     population: Vec<Pop>,
 }
 
 impl Planet {
-    ///returns an empty planet
+    ///returns the next planet of the save, with the pops living on it
     fn new<'a>(
         it: &mut impl Iterator<Item = &'a str>,
         pops: &mut HashMap<usize, Vec<Pop>>,
-    ) -> Result<Option<(Planet, bool)>, String> {
+    ) -> Result<Option<Planet>, String> {
         let id = match it.next() {
             Some("\t}") => return Ok(None),
             Some(line) => match line.get(2..(line.chars().count() - 2)) {
                 Some(a) => parser(a)?,
                 None => return Err(format!("Couldn't parse planet ID: >{}<", line)),
             },
-            None => return Err(format!("Couldn't parse planet ID; end of iterator")),
+            None => return Err("Couldn't parse planet ID; end of iterator".to_string()),
         };
-        let mut ret = Planet {
-            id: id,
-            population: match pops.remove(&id) {
-                Some(a) => a,
-                None => Vec::new(),
-            },
-            name: String::new(),
-            typ: String::new(),
-            size: 0,
-        };
-        while let Some(line) = it.next() {
+        let mut ret = Planet::newe();
+        ret.id = id;
+        ret.population = pops.remove(&id).unwrap_or_default();
+        for line in it.by_ref() {
             if line == "\t\t}" {
                 break;
             }
@@ -596,6 +947,25 @@ impl Planet {
                         None => return Err(format!("Couldn't get the name from line: >{}<", line)),
                     }
                 }
+                Some("orbit") if !line.ends_with('{') => {
+                    ret.orbit = match line.get(9..line.chars().count()) {
+                        Some(a) => str_to_coord(a)?,
+                        None => {
+                            return Err(format!("Couldn't get the orbit from line: >{}<", line))
+                        }
+                    }
+                }
+                Some("owner") => ret.colonized = true,
+                Some("stabi") => {
+                    ret.stability = match line.get(13..line.chars().count()) {
+                        Some(a) => a.parse().map_err(|e| {
+                            format!("Couldn't parse the stability >{}<: >{}<", a, e)
+                        })?,
+                        None => {
+                            return Err(format!("Couldn't get the stability from line: >{}<", line))
+                        }
+                    }
+                }
                 Some("plane") => match line.get(10..14) {
                     Some("size") => {
                         ret.size = match line.get(15..line.chars().count()) {
@@ -624,26 +994,28 @@ impl Planet {
                 _ => {}
             }
         }
-        Ok(match ret.population.len() {
-            0 => Some((ret, false)),
-            _ => Some((ret, true)),
-        })
+        ret.colonized |= !ret.population.is_empty();
+        Ok(Some(ret))
     }
     fn newe() -> Planet {
         Planet {
-            id: std::usize::MAX,
+            id: usize::MAX,
             population: Vec::new(),
             name: String::new(),
             typ: String::new(),
             size: 0,
+            orbit: 0,
+            colonized: false,
+            stability: 0.0,
+            deposits: Vec::new(),
         }
     }
     fn read(it: &mut impl Iterator<Item = String>) -> io::Result<Option<Planet>> {
         let mut ret = Planet::newe();
         ret.id = match it.next().unwrap().get(..) {
-            Some("\t\t\t}") => return Ok(None),
+            Some("\t}") => return Ok(None),
             Some(a) => parser(
-                a.get(4..(a.chars().count() - 2))
+                a.get(2..(a.chars().count() - 2))
                     .expect("Error in planet id , planet"),
             )
             .expect("Test"),
@@ -651,26 +1023,47 @@ impl Planet {
         };
         ret.name = match it.next().unwrap().get(..) {
             Some(a) => a
-                .get(5..a.chars().count())
+                .get(3..a.chars().count())
                 .expect("Error in planet id , planet")
                 .to_owned(),
             None => panic!("fuck"),
         };
         ret.typ = match it.next().unwrap().get(..) {
             Some(a) => a
-                .get(5..a.chars().count())
+                .get(3..a.chars().count())
                 .expect("Error in planet id , planet")
                 .to_owned(),
             None => panic!("fuck"),
         };
         ret.size = match it.next().unwrap().get(..) {
             Some(a) => parser(
-                a.get(5..a.chars().count())
+                a.get(3..a.chars().count())
                     .expect("Error in planet id , planet"),
             )
             .unwrap(),
             None => panic!("fuck"),
         };
+        ret.orbit = match it.next().unwrap().get(3..) {
+            Some(a) => a.parse().expect("Error in planet orbit, planet"),
+            None => panic!("fuck"),
+        };
+        ret.colonized = match it.next().unwrap().get(3..) {
+            Some("true") => true,
+            Some("false") => false,
+            _ => panic!("Despacito, but colonized"),
+        };
+        ret.stability = match it.next().unwrap().get(3..) {
+            Some(a) => a.parse().expect("Error in planet stability, planet"),
+            None => panic!("fuck"),
+        };
+        it.next();
+        loop {
+            match it.next().unwrap().get(..) {
+                Some("\t\t\t}") => break,
+                Some(a) => ret.deposits.push(a.get(4..).unwrap().to_owned()),
+                None => panic!("fuck"),
+            }
+        }
         it.next();
         while let Some(a) = Pop::read(it)? {
             ret.population.push(a);
@@ -678,23 +1071,62 @@ impl Planet {
         it.next();
         Ok(Some(ret))
     }
+    ///number of pops living on the planet, counting every member of a pop group.
+    fn pop_count(&self) -> usize {
+        self.population.iter().map(|x| x.size).sum()
+    }
+    ///how well the species can live on the planet, from 0 to 1. Robotic species without
+    /// a climate preference can live on any habitable class.
+    fn habitability(&self, species: &Species) -> f64 {
+        match self.typ.as_str() {
+            "pc_gaia" | "pc_ringworld_habitable" | "pc_city" => return 1.0,
+            "pc_habitat" => return 0.7,
+            "pc_nuked" => return 0.1,
+            _ => {}
+        }
+        let climate = match CLIMATES.iter().find(|x| x.contains(&self.typ.as_str())) {
+            Some(a) => a,
+            None => return 0.0,
+        };
+        match species.preference() {
+            Some(a) if a == self.typ => 0.8,
+            Some(a) if climate.contains(&a) => 0.6,
+            Some(_) => 0.2,
+            None => 1.0,
+        }
+    }
     fn save(&self, save: &mut File) -> io::Result<()> {
         save.write_all(
             format!(
-                "\t\t\t\t{} {{\n\t\t\t\t\t{}\n\t\t\t\t\t{}\n\t\t\t\t\t{}\n\t\t\t\t\tpops {{\n",
-                self.id, self.name, self.typ, self.size
+                "\t\t{} {{\n\t\t\t{}\n\t\t\t{}\n\t\t\t{}\n\t\t\t{}\n\t\t\t{}\n\t\t\t{}\n\t\t\tdeposits {{\n",
+                self.id, self.name, self.typ, self.size, self.orbit, self.colonized, self.stability
             )
             .as_bytes(),
         )?;
+        for i in self.deposits.iter() {
+            save.write_all(format!("\t\t\t\t{}\n", i).as_bytes())?;
+        }
+        save.write_all(b"\t\t\t}\n\t\t\tpops {\n")?;
         for i in self.population.iter() {
             i.save(save)?;
         }
-        save.write_all(b"\t\t\t\t\t}\n\t\t\t\t}\n")?;
+        save.write_all(b"\t\t\t}\n\t\t}\n")?;
         Ok(())
     }
 }
 
-///a pop.
+///the habitable planet classes, grouped by climate (dry, wet, cold).
+const CLIMATES: [[&str; 3]; 3] = [
+    ["pc_desert", "pc_arid", "pc_savannah"],
+    ["pc_tropical", "pc_continental", "pc_ocean"],
+    ["pc_tundra", "pc_arctic", "pc_alpine"],
+];
+
+///a pop. Saves from before pop groups have one Pop per pop, with a size of 1;
+/// newer saves have one Pop per group, with size being the number of pops in the group.
+/// Pops without an ethic of their own, as those of hive minds, machine intelligences and robots
+/// are, follow Ethic::Gestalt, which stands for having none. Individual pops have always been
+/// read that way, so pop groups are too, and both layouts count them alike.
 #[derive(Clone)]
 struct Pop {
     id: usize,
@@ -703,20 +1135,22 @@ struct Pop {
     job: String,
     category: String,
     slave: bool,
+    size: usize,
 }
 
 impl Pop {
     fn new<'a>(it: &mut impl Iterator<Item = &'a str>, id: usize) -> Result<(usize, Pop), String> {
         let mut ret = Pop {
-            id: id,
-            species: std::usize::MAX,
+            id,
+            species: usize::MAX,
             ethic: Ethic::Gestalt,
             job: String::new(),
             category: String::new(),
             slave: false,
+            size: 1,
         };
-        let mut planet = std::usize::MAX;
-        while let Some(line) = it.next() {
+        let mut planet = usize::MAX;
+        for line in it.by_ref() {
             match line.get(..7) {
                 Some("\t\tspeci") => {
                     ret.species = match line.get(16..(line.chars().count())) {
@@ -754,29 +1188,62 @@ impl Pop {
                         None => return Err(format!("Couldn't get enslavement from: >{}<", line)),
                     }
                 }
-                None => {
-                    if line == "\t}" {
-                        break;
-                    }
+                None if line == "\t}" => {
+                    break;
                 }
                 _ => {}
             }
         }
         Ok((planet, ret))
     }
+    ///reads a single entry of "pop_groups={ ... }". Groups nest their species, ethic and
+    /// category in sub-blocks, so lines are matched on their key regardless of indentation.
+    /// The species is its id in species_db, not yet where it is among the species.
+    fn new_group<'a>(
+        it: &mut impl Iterator<Item = &'a str>,
+        id: usize,
+    ) -> Result<(usize, Pop), String> {
+        let mut ret = Pop::newe();
+        ret.id = id;
+        ret.size = 0;
+        let mut planet = usize::MAX;
+        for line in it {
+            if line == "\t}" {
+                break;
+            }
+            let mut temp = line.trim_start_matches('\t').splitn(2, '=');
+            let (key, value) = match (temp.next(), temp.next()) {
+                (Some(_), Some("{")) | (_, None) => continue,
+                (Some(a), Some(b)) => (a, b.trim_matches('"')),
+                _ => continue,
+            };
+            match key {
+                "species" => ret.species = parser(value)?,
+                "ethic" => ret.ethic = Ethic::str_to_ethic(value),
+                "job" => ret.job = value.to_owned(),
+                "category" => ret.category = value.to_owned(),
+                "planet" => planet = parser(value)?,
+                "enslaved" => ret.slave = str_to_bool(value)?,
+                "size" => ret.size = parser(value)?,
+                _ => {}
+            }
+        }
+        Ok((planet, ret))
+    }
     fn newe() -> Pop {
         Pop {
-            id: std::usize::MAX,
-            species: std::usize::MAX,
+            id: usize::MAX,
+            species: usize::MAX,
             ethic: Ethic::Gestalt,
             job: String::new(),
             category: String::new(),
             slave: false,
+            size: 1,
         }
     }
     fn read(it: &mut impl Iterator<Item = String>) -> io::Result<Option<Pop>> {
         match it.next().unwrap().get(..) {
-            Some("\t\t\t\t\t}") => Ok(None),
+            Some("\t\t\t}") => Ok(None),
             Some(a) => {
                 let mut ret = Pop::newe();
                 let mut temp = a.split("\t");
@@ -790,6 +1257,10 @@ impl Pop {
                     Some("true") => true,
                     _ => panic!("Despacito, but bool"),
                 };
+                // save.txt written before pop groups has no size column
+                if let Some(Ok(a)) = temp.next().map(parser) {
+                    ret.size = a;
+                }
                 Ok(Some(ret))
             }
             None => panic!("D?+++spacito"),
@@ -798,8 +1269,8 @@ impl Pop {
     fn save(&self, save: &mut File) -> io::Result<()> {
         save.write_all(
             format!(
-                "{}\t{}\t{}\t{}\t{}\t{}\t\n",
-                self.id, self.species, self.ethic, self.job, self.category, self.slave
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t\n",
+                self.id, self.species, self.ethic, self.job, self.category, self.slave, self.size
             )
             .as_bytes(),
         )?;
@@ -838,7 +1309,7 @@ impl Species {
         ret.adjective = it.next().unwrap().get(3..).unwrap().to_owned();
         ret.portrait = it.next().unwrap().get(3..).unwrap().to_owned();
         it.next();
-        while let Some(line) = it.next() {
+        for line in it.by_ref() {
             match line.get(..).unwrap() {
                 "\t\t\t}" => break,
                 _ => ret.traits.push(line.get(4..).unwrap().to_owned()),
@@ -861,37 +1332,47 @@ impl Species {
         save.write_all(b"\t\t\t}\n\t\t}\n")?;
         Ok(())
     }
+    ///the planet class the species prefers, read from its climate preference trait.
+    fn preference(&self) -> Option<&str> {
+        self.traits
+            .iter()
+            .find(|x| x.starts_with("trait_pc_") && x.ends_with("_preference"))
+            .and_then(|x| x.get(6..(x.chars().count() - 11)))
+    }
     ///takes a string in the format of a stellaris save, extracting the species name.
     fn set_name(&mut self, inp: &str) {
-        self.name = format!("{}", inp.get(8..(inp.chars().count() - 1)).unwrap());
+        self.name = inp.get(8..(inp.chars().count() - 1)).unwrap().to_string();
     }
     ///takes a string in the format of a stellaris save, extracting the species plural.
     fn set_plural(&mut self, inp: &str) {
-        self.plural = format!("{}", inp.get(10..(inp.chars().count() - 1)).unwrap());
+        self.plural = inp.get(10..(inp.chars().count() - 1)).unwrap().to_string();
     }
     ///takes a string in the format of a stellaris save, extracting the species adjective.
     fn set_adjective(&mut self, inp: &str) {
-        self.adjective = format!("{}", inp.get(13..(inp.chars().count() - 1)).unwrap());
+        self.adjective = inp.get(13..(inp.chars().count() - 1)).unwrap().to_string();
     }
     ///takes a string in the format of a stellaris save, extracting the species portrait.
     fn set_portrait(&mut self, inp: &str) {
-        self.portrait = format!("{}", inp.get(12..(inp.chars().count() - 1)).unwrap());
+        self.portrait = inp.get(12..(inp.chars().count() - 1)).unwrap().to_string();
     }
     ///takes an iterator of gamestate at the correct position,
     /// extracting species traits in the form of strings.
     fn set_traits<'a>(&mut self, inp: &mut impl Iterator<Item = &'a str>) {
-        while let Some(line) = inp.next() {
+        for line in inp.by_ref() {
             if line == "\t\t}" {
                 break;
             }
-            self.traits.push(format!(
-                "{}",
-                line.get(10..(line.chars().count() - 1)).unwrap()
-            ));
+            self.traits.push(
+                line.get(10..(line.chars().count() - 1))
+                    .unwrap()
+                    .to_string(),
+            );
         }
     }
 }
 
+///a star system; owner is the id of the empire whose starbase claims it, if any,
+/// and hyperlanes holds the ids of the systems it has a hyperlane to.
 #[derive(Debug, Clone)]
 pub struct GalObject {
     id: usize,
@@ -899,7 +1380,9 @@ pub struct GalObject {
     y: i64,
     typ: String,
     name: String,
+    owner: Option<usize>,
     planets: Vec<usize>,
+    hyperlanes: Vec<usize>,
 }
 
 impl GalObject {
@@ -911,12 +1394,14 @@ impl GalObject {
     }
     fn new<'a>(it: &mut impl Iterator<Item = &'a str>) -> Result<Option<GalObject>, String> {
         let mut ret = GalObject {
-            id: std::usize::MAX,
-            x: std::i64::MAX,
-            y: std::i64::MAX,
+            id: usize::MAX,
+            x: i64::MAX,
+            y: i64::MAX,
             typ: String::new(),
             name: String::new(),
+            owner: None,
             planets: Vec::new(),
+            hyperlanes: Vec::new(),
         };
         ret.id = match it.next() {
             Some("}") => return Ok(None),
@@ -924,13 +1409,9 @@ impl GalObject {
                 Some(a) => parser(a)?,
                 None => return Err(format!("Couldn't parse galactic object ID: >{}<", line)),
             },
-            None => {
-                return Err(format!(
-                    "Couldn't parse galactic object ID; end of iterator"
-                ))
-            }
+            None => return Err("Couldn't parse galactic object ID; end of iterator".to_string()),
         };
-        while let Some(line) = it.next() {
+        for line in it.by_ref() {
             if line == "\t}" {
                 break;
             }
@@ -988,10 +1469,20 @@ impl GalObject {
                         ))
                     }
                 }),
-                None => {
-                    if line == "\t}" {
-                        break;
-                    }
+                Some("\t\t\t\tt") if line.starts_with("\t\t\t\tto=") => {
+                    ret.hyperlanes
+                        .push(match line.get(7..line.chars().count()) {
+                            Some(a) => parser(a)?,
+                            None => {
+                                return Err(format!(
+                                    "Couldn't get hyperlane of line >{}< for GalObj >{}<",
+                                    line, ret.id
+                                ))
+                            }
+                        })
+                }
+                None if line == "\t}" => {
+                    break;
                 }
                 _ => {}
             }
@@ -1000,12 +1491,14 @@ impl GalObject {
     }
     fn newe() -> GalObject {
         GalObject {
-            id: std::usize::MAX,
-            x: std::i64::MAX,
-            y: std::i64::MAX,
+            id: usize::MAX,
+            x: i64::MAX,
+            y: i64::MAX,
             typ: String::new(),
             name: String::new(),
+            owner: None,
             planets: Vec::new(),
+            hyperlanes: Vec::new(),
         }
     }
     fn read(it: &mut impl Iterator<Item = String>) -> io::Result<Option<GalObject>> {
@@ -1032,6 +1525,11 @@ impl GalObject {
         let mut temp = temp.get(4..(temp.chars().count() - 1)).unwrap().split(", ");
         ret.x = temp.next().unwrap().parse().unwrap();
         ret.y = temp.next().unwrap().parse().unwrap();
+        ret.owner = match it.next().unwrap().get(3..) {
+            Some("None") => None,
+            Some(a) => Some(parser(a).expect("Error in owner, GalObject")),
+            None => panic!("fuck"),
+        };
         it.next();
         loop {
             match it.next().unwrap().get(..) {
@@ -1041,6 +1539,14 @@ impl GalObject {
             }
         }
         it.next();
+        loop {
+            match it.next().unwrap().get(..) {
+                Some("\t\t\t}") => break,
+                Some(a) => ret.hyperlanes.push(a.get(4..).unwrap().parse().unwrap()),
+                _ => panic!("NO"),
+            }
+        }
+        it.next();
         Ok(Some(ret))
     }
     fn save(&self, save: &mut File) -> io::Result<()> {
@@ -1051,16 +1557,36 @@ impl GalObject {
             )
             .as_bytes(),
         )?;
+        match self.owner {
+            Some(a) => save.write_all(format!("\t\t\t{}\n", a).as_bytes())?,
+            None => save.write_all(b"\t\t\tNone\n")?,
+        }
         save.write_all(b"\t\t\tplanets {\n")?;
         for i in self.planets.iter() {
             save.write_all(format!("\t\t\t\t{}\n", i).as_bytes())?;
         }
+        save.write_all(b"\t\t\t}\n\t\t\thyperlanes {\n")?;
+        for i in self.hyperlanes.iter() {
+            save.write_all(format!("\t\t\t\t{}\n", i).as_bytes())?;
+        }
         save.write_all(b"\t\t\t}\n\t\t}\n")?;
         Ok(())
     }
     pub fn get_name(&self) -> &str {
         &self.name
     }
+    pub fn typ(&self) -> &str {
+        &self.typ
+    }
+    pub fn id(&self) -> usize {
+        self.id
+    }
+    pub fn owner(&self) -> Option<usize> {
+        self.owner
+    }
+    pub fn get_hyperlane_iter(&self) -> impl Iterator<Item = &usize> {
+        self.hyperlanes.iter()
+    }
 }
 
 ///reads coordinates on the form "140.540" to i64, going via f64 and multiplying by 1000. Returns an Err if parsing fails.
@@ -1108,6 +1634,13 @@ fn open_zip(filename: PathBuf) -> zip::result::ZipResult<String> {
     Ok(temp)
 }
 
+///returns the key counted most often, the last of them on a tie.
+fn most<K: Ord>(inp: BTreeMap<K, usize>) -> Option<K> {
+    inp.into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(x, _)| x)
+}
+
 ///turns &str "yes" and "no" to bool false and true, returning an error if neither
 fn str_to_bool(inp: &str) -> Result<bool, String> {
     match inp {
@@ -1117,6 +1650,22 @@ fn str_to_bool(inp: &str) -> Result<bool, String> {
     }
 }
 
+///first game version whose saves store pop groups instead of individual pops.
+const POP_GROUP_VERSION: (usize, usize) = (4, 0);
+
+///reads the (major, minor) game version from the first line of gamestate,
+/// on the form version="Shelley v2.5.1".
+fn save_version(inp: &str) -> Result<(usize, usize), String> {
+    let mut temp = match inp.rsplit(" v").next() {
+        Some(a) => a.trim_end_matches('"').split('.'),
+        None => return Err(format!("Couldn't find version in: >{}<", inp)),
+    };
+    match (temp.next(), temp.next()) {
+        (Some(major), Some(minor)) => Ok((parser(major)?, parser(minor)?)),
+        _ => Err(format!("Couldn't read version from: >{}<", inp)),
+    }
+}
+
 ///parses a &str to a number, changing the Result-type.
 fn parser(inp: &str) -> Result<usize, String> {
     match inp.parse() {
@@ -1146,7 +1695,29 @@ fn pop_analyser<'a>(
                 None => return Err(format!("Couldn't get species id from: >{}<", line)),
             },
         )?;
-        ret.entry(id).or_insert(Vec::new()).push(temp);
+        ret.entry(id).or_insert_with(Vec::new).push(temp);
+    }
+    Ok(ret)
+}
+
+///takes an iterator over the file, reading everything between "pop_groups={ ... }",
+/// returning the same mapping of planet id to pops as pop_analyser.
+fn pop_group_analyser<'a>(
+    it: &mut impl Iterator<Item = &'a str>,
+) -> Result<HashMap<usize, Vec<Pop>>, String> {
+    let mut ret = HashMap::new();
+    while let Some(line) = it.next() {
+        if line == "}" {
+            break;
+        }
+        let (id, temp) = Pop::new_group(
+            it,
+            match line.get(1..(line.chars().count() - 2)) {
+                Some(a) => parser(a)?,
+                None => return Err(format!("Couldn't get pop group id from: >{}<", line)),
+            },
+        )?;
+        ret.entry(id).or_insert_with(Vec::new).push(temp);
     }
     Ok(ret)
 }
@@ -1162,12 +1733,10 @@ fn gal_obj_analyser<'a>(it: &mut impl Iterator<Item = &'a str>) -> Result<Vec<Ga
 fn planet_analyser<'a>(
     it: &mut impl Iterator<Item = &'a str>,
     pops: &mut HashMap<usize, Vec<Pop>>,
-) -> Result<HashMap<usize, Planet>, String> {
-    let mut ret = HashMap::new();
+) -> Result<BTreeMap<usize, Planet>, String> {
+    let mut ret = BTreeMap::new();
     while let Some(plan) = Planet::new(it, pops)? {
-        if plan.1 {
-            ret.insert(plan.0.id, plan.0);
-        }
+        ret.insert(plan.id, plan);
     }
     Ok(ret)
 }
@@ -1175,18 +1744,96 @@ fn planet_analyser<'a>(
 fn empire_analyser<'a>(
     it: &mut impl Iterator<Item = &'a str>,
     inp: &mut Galaxy,
-    plan: &mut HashMap<usize, Planet>,
 ) -> Result<(), Box<dyn Error>> {
-    while let Some(a) = Empire::new(it, plan)? {
+    while let Some(a) = Empire::new(it)? {
         inp.push(a);
     }
     Ok(())
 }
 
+///takes an iterator over the file, reading everything between "starbases={ ... }",
+/// giving each system the owner of the starbase in it.
+fn starbase_analyser<'a>(
+    it: &mut impl Iterator<Item = &'a str>,
+    obj: &mut [GalObject],
+) -> Result<(), String> {
+    let mut system = usize::MAX;
+    let mut owner = None;
+    for line in it {
+        match line.get(..8) {
+            Some("\t\tsystem") => {
+                system = match line.get(9..line.chars().count()) {
+                    Some(a) => parser(a)?,
+                    None => return Err(format!("Couldn't get starbase system from: >{}<", line)),
+                }
+            }
+            Some("\t\towner=") => {
+                owner = match line.get(8..line.chars().count()) {
+                    Some(a) => Some(parser(a)?),
+                    None => return Err(format!("Couldn't get starbase owner from: >{}<", line)),
+                }
+            }
+            _ => match line {
+                "}" => break,
+                "\t}" => {
+                    if let Some(a) = obj.iter_mut().find(|x| x.id == system) {
+                        a.owner = owner.take();
+                    }
+                    system = usize::MAX;
+                }
+                _ => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+///takes an iterator over the file, reading everything between "deposit={ ... }",
+/// adding the type of each deposit to the planet it sits on.
+fn deposit_analyser<'a>(
+    it: &mut impl Iterator<Item = &'a str>,
+    plan: &mut BTreeMap<usize, Planet>,
+) -> Result<(), String> {
+    let mut typ = String::new();
+    let mut planet = usize::MAX;
+    for line in it {
+        match line.get(..7) {
+            Some("\t\ttype=") => {
+                typ = match line.get(8..(line.chars().count() - 1)) {
+                    Some(a) => a.to_owned(),
+                    None => return Err(format!("Couldn't get deposit type from: >{}<", line)),
+                }
+            }
+            Some("\t\tplane") => {
+                planet = match line.get(9..line.chars().count()) {
+                    Some(a) => parser(a)?,
+                    None => return Err(format!("Couldn't get deposit planet from: >{}<", line)),
+                }
+            }
+            _ => match line {
+                "}" => break,
+                "\t}" => {
+                    if let Some(a) = plan.get_mut(&planet) {
+                        a.deposits.push(std::mem::take(&mut typ));
+                    }
+                    planet = usize::MAX;
+                }
+                _ => {}
+            },
+        }
+    }
+    Ok(())
+}
+
 ///takes the contents of gamestate in a .sav file, returning a Galaxy packed in a result
 fn save_analyser(file: &str, cmp: &mut Everything) -> Result<Option<Galaxy>, Box<dyn Error>> {
     let mut ret = Galaxy::new();
-    let mut it = file.split('\n').skip(3);
+    let mut it = file.split('\n');
+    let groups = match it.next() {
+        Some(line) => save_version(line)? >= POP_GROUP_VERSION,
+        None => return Err("Empty gamestate".into()),
+    };
+    let mut it = it.skip(2);
     match it.next() {
         Some(line) => ret.setdate(line).expect("Sorry"),
         None => panic!("Error getting next line at: Get_Date"),
@@ -1195,44 +1842,63 @@ fn save_analyser(file: &str, cmp: &mut Everything) -> Result<Option<Galaxy>, Box
         return Ok(None);
     }
     let mut pops = HashMap::new();
-    let mut planets = HashMap::new();
     let mut temp = Vec::new();
+    let mut species = HashMap::new();
     while let Some(line1) = it.next() {
         match line1 {
             "species={" => ret.get_species(&mut it),
-            "pop={" => pops = pop_analyser(&mut it)?,
+            "species_db={" => species = ret.get_species_db(&mut it)?,
+            "pop={" if !groups => pops = pop_analyser(&mut it)?,
+            "pop_groups={" if groups => pops = pop_group_analyser(&mut it)?,
             "galactic_object={" => temp = gal_obj_analyser(&mut it)?,
-            "\tplanet={" => {
-                planets = planet_analyser(&mut it, &mut pops)?;
-                for plan in temp.iter_mut() {
-                    plan.planets.retain(|x| planets.get(x).is_some());
-                }
-            }
-            "country={" => empire_analyser(&mut it, &mut ret, &mut planets)?,
+            "starbases={" => starbase_analyser(&mut it, &mut temp)?,
+            "\tplanet={" => ret.planets = planet_analyser(&mut it, &mut pops)?,
+            "country={" => empire_analyser(&mut it, &mut ret)?,
+            "deposit={" => deposit_analyser(&mut it, &mut ret.planets)?,
             _ => {}
         }
     }
+    // pop groups name their species by its id in species_db
+    if groups {
+        for pop in ret
+            .planets
+            .values_mut()
+            .flat_map(|x| x.population.iter_mut())
+        {
+            pop.species = species.get(&pop.species).copied().unwrap_or(usize::MAX);
+        }
+    }
     ret.push_g(temp);
     Ok(Some(ret))
 }
 
+///the version of what save.txt holds, on its first line. It goes up whenever anything is kept
+/// differently, and a save.txt of another version is left out, so every save is read again.
+const CACHE_VERSION: usize = 4;
+
 fn read() -> Result<Everything, Box<dyn Error>> {
-    Ok(Everything::read(
-        &mut BufReader::new(File::open("save.txt")?)
-            .lines()
-            .map(|x| x.expect("Error in BufReader")),
-    )?)
+    let mut it = BufReader::new(File::open("save.txt")?)
+        .lines()
+        .map(|x| x.expect("Error in BufReader"));
+    if it.next() != Some(format!("version {}", CACHE_VERSION)) {
+        return Ok(Everything::new());
+    }
+    Everything::read(&mut it)
 }
 
 impl fmt::Debug for Planet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
+        writeln!(
             f,
-            "{}\t{}\t{}\t{}\n",
-            self.id, self.name, self.typ, self.size
+            "{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.name,
+            self.typ,
+            self.size,
+            self.pop_count()
         )?;
         for i in self.population.iter() {
-            write!(f, "{:?}\n", i)?;
+            writeln!(f, "{:?}", i)?;
         }
         write!(f, "")
     }
@@ -1241,8 +1907,8 @@ impl fmt::Debug for Pop {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{},\t{},\t{},\t{},\t{},\t{}",
-            self.id, self.species, self.ethic, self.job, self.category, self.slave
+            "{},\t{},\t{},\t{},\t{},\t{},\t{}",
+            self.id, self.species, self.ethic, self.job, self.category, self.slave, self.size
         )
     }
 }
@@ -1260,3 +1926,198 @@ impl fmt::Debug for Empire {
         )
     }
 }
+
+#[cfg(test)]
+impl Galaxy {
+    ///reads the galaxy from the text of a gamestate, panicking if that fails.
+    /// Its bounds are those of its systems, as reader gives every galaxy those of the first.
+    pub fn gamestate(file: &str) -> Galaxy {
+        let mut ret = save_analyser(file, &mut Everything::new())
+            .expect("Couldn't read the gamestate")
+            .expect("The gamestate was read before");
+        let xs = ret.obj.iter().map(|x| x.x);
+        let ys = ret.obj.iter().map(|x| x.y);
+        ret.update_max([
+            xs.clone().fold(0, i64::min),
+            xs.fold(0, i64::max),
+            ys.clone().fold(0, i64::min),
+            ys.fold(0, i64::max),
+        ]);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///a save with pop groups, cut down to the species, the pop groups, the planets and their
+    /// deposits. Only Earth is lived on.
+    const GROUPS: &str = "version=\"Corvus v4.0.2\"
+version_control_revision=1
+name=\"Test\"
+date=\"2230.01.01\"
+species_db={
+\t16777216={
+\t\tname={
+\t\t\tkey=\"SPEC_Human\"
+\t\t}
+\t\tplural={
+\t\t\tkey=\"SPEC_Human_pl\"
+\t\t}
+\t\tportrait=\"human\"
+\t\ttraits={
+\t\t\ttrait=\"trait_adaptive\"
+\t\t\ttrait=\"trait_pc_continental_preference\"
+\t\t}
+\t}
+\t50331649={
+\t\tname=\"Blorg\"
+\t\tplural=\"Blorgs\"
+\t\tadjective=\"Blorgian\"
+\t}
+}
+pop_groups={
+\t3={
+\t\tkey={
+\t\t\tspecies=50331649
+\t\t\tcategory=\"worker\"
+\t\t\tethos={
+\t\t\t\tethic=\"ethic_materialist\"
+\t\t\t}
+\t\t}
+\t\tplanet=5
+\t\tsize=12
+\t}
+\t4={
+\t\tkey={
+\t\t\tspecies=16777216
+\t\t\tcategory=\"ruler\"
+\t\t}
+\t\tplanet=5
+\t\tsize=3
+\t}
+}
+planets={
+\tplanet={
+\t\t5={
+\t\t\tname=\"Earth\"
+\t\t\tplanet_class=\"pc_continental\"
+\t\t\torbit=30.000
+\t\t\tplanet_size=16
+\t\t\towner=0
+\t\t}
+\t\t6={
+\t\t\tname=\"Tethys\"
+\t\t\tplanet_class=\"pc_ocean\"
+\t\t\torbit=-45.500
+\t\t\tplanet_size=12
+\t\t}
+\t\t7={
+\t\t\tname=\"Jupiter\"
+\t\t\tplanet_class=\"pc_gas_giant\"
+\t\t\torbit=120.000
+\t\t\tplanet_size=30
+\t\t}
+\t\t8={
+\t\t\tname=\"Arrakis\"
+\t\t\tplanet_class=\"pc_desert\"
+\t\t\tplanet_size=20
+\t\t}
+\t}
+}
+deposit={
+\t0={
+\t\ttype=\"d_minerals_3\"
+\t\tplanet=6
+\t}
+\t1={
+\t\ttype=\"d_exotic_gases_2\"
+\t\tplanet=7
+\t}
+\t2={
+\t\ttype=\"d_energy_1\"
+\t\tplanet=6
+\t}
+\t3={
+\t\ttype=\"d_alloys_1\"
+\t\tplanet=99
+\t}
+}
+";
+
+    #[test]
+    fn pop_groups_count_their_size() {
+        let galaxy = Galaxy::gamestate(GROUPS);
+        let planet = &galaxy.planets[&5];
+        assert_eq!(planet.pop_count(), 15);
+        assert!(planet.colonized);
+        // the ruler group has no ethos, so it has no ethic, see Pop
+        let ethics: Vec<&Ethic> = planet.population.iter().map(|x| &x.ethic).collect();
+        assert_eq!(ethics, [&Ethic::Materialist, &Ethic::Gestalt]);
+    }
+
+    #[test]
+    fn pop_groups_find_their_species_in_species_db() {
+        let galaxy = Galaxy::gamestate(GROUPS);
+        let species: BTreeMap<&str, usize> = galaxy.planets[&5]
+            .population
+            .iter()
+            .map(|x| (galaxy.species_name(x.species).unwrap(), x.size))
+            .collect();
+        assert_eq!(species, BTreeMap::from([("Blorg", 12), ("SPEC_Human", 3)]));
+        assert_eq!(
+            galaxy.species[0].traits,
+            ["trait_adaptive", "trait_pc_continental_preference"]
+        );
+        assert_eq!(galaxy.species[1].adjective, "Blorgian");
+    }
+
+    #[test]
+    fn planets_without_pops_are_kept() {
+        let galaxy = Galaxy::gamestate(GROUPS);
+        let ids: Vec<usize> = galaxy.planets.keys().copied().collect();
+        assert_eq!(ids, [5, 6, 7, 8]);
+        let ocean = &galaxy.planets[&6];
+        assert_eq!(ocean.name, "Tethys");
+        assert_eq!(ocean.typ, "pc_ocean");
+        assert_eq!(ocean.size, 12);
+        assert_eq!(ocean.orbit, -45500);
+        assert!(!ocean.colonized);
+        assert_eq!(ocean.pop_count(), 0);
+        assert_eq!(galaxy.planets[&5].orbit, 30000);
+        // a planet without an orbit line stays at 0
+        assert_eq!(galaxy.planets[&8].orbit, 0);
+    }
+
+    #[test]
+    fn deposits_go_to_their_planets() {
+        let galaxy = Galaxy::gamestate(GROUPS);
+        assert_eq!(galaxy.planets[&6].deposits, ["d_minerals_3", "d_energy_1"]);
+        assert_eq!(galaxy.planets[&7].deposits, ["d_exotic_gases_2"]);
+        assert!(galaxy.planets[&5].deposits.is_empty());
+    }
+
+    #[test]
+    fn habitability_follows_the_climate_preference() {
+        let galaxy = Galaxy::gamestate(GROUPS);
+        let (human, blorg) = (&galaxy.species[0], &galaxy.species[1]);
+        assert_eq!(human.preference(), Some("pc_continental"));
+        assert_eq!(blorg.preference(), None);
+        let habitability = |id: usize| {
+            let planet = &galaxy.planets[&id];
+            (planet.habitability(human), planet.habitability(blorg))
+        };
+        assert_eq!(habitability(5), (0.8, 1.0));
+        assert_eq!(habitability(6), (0.6, 1.0));
+        assert_eq!(habitability(7), (0.0, 0.0));
+        assert_eq!(habitability(8), (0.2, 1.0));
+    }
+
+    #[test]
+    fn save_version_reads_major_and_minor() {
+        assert_eq!(save_version("version=\"Shelley v2.5.1\""), Ok((2, 5)));
+        assert_eq!(save_version("version=\"Corvus v4.0.2\""), Ok((4, 0)));
+        assert!(save_version("version=\"Shelley\"").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::saveread::Galaxy;
+use std::cmp::Reverse;
+
+///the most results a search returns.
+const RESULTS: usize = 50;
+
+///returns what matches the query in the galaxy, best first, as labels saying what they are
+/// with the id of the system to show for them, see Galaxy::targets. Only the name in the label
+/// is matched, not what it says it is.
+pub fn find(galaxy: &Galaxy, query: &str) -> Vec<(String, usize)> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut ret: Vec<(i64, String, usize)> = galaxy
+        .targets()
+        .into_iter()
+        .filter_map(|(label, id)| {
+            let name = label.split_once(": ").map_or(label.as_str(), |(_, x)| x);
+            Some((score(query, name)?, label, id))
+        })
+        .collect();
+    ret.sort_by(|a, b| (Reverse(a.0), &a.1).cmp(&(Reverse(b.0), &b.1)));
+    ret.dedup_by(|a, b| a.1 == b.1 && a.2 == b.2);
+    ret.into_iter()
+        .take(RESULTS)
+        .map(|(_, label, id)| (label, id))
+        .collect()
+}
+
+///scores how well the query matches the text, ignoring case. Every character of the query has
+/// to be found in the text in the same order, and matches score higher the more of those
+/// follow each other and start words, and the fewer characters are skipped between them.
+/// Returns None if the query isn't found.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let mut ret = 0;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+    for c in query.iter() {
+        let i = next + text[next..].iter().position(|x| x == c)?;
+        ret += 1;
+        match last {
+            Some(a) if a + 1 == i => ret += 5,
+            Some(a) => ret -= (i - a - 1).min(5) as i64,
+            None => ret -= i.min(5) as i64,
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            ret += 8;
+        }
+        last = Some(i);
+        next = i + 1;
+    }
+    // the whole name matching counts most, and the query found in one piece next
+    if text == query {
+        ret += 50;
+    } else if text.windows(query.len()).any(|x| x == query.as_slice()) {
+        ret += 20;
+    }
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///a save cut down to two systems, a planet and the species living there.
+    const SAVE: &str = "version=\"Corvus v4.0.2\"
+version_control_revision=1
+name=\"Test\"
+date=\"2230.01.01\"
+species_db={
+\t7={
+\t\tname=\"Human\"
+\t\tplural=\"Humans\"
+\t}
+}
+pop_groups={
+\t3={
+\t\tkey={
+\t\t\tspecies=7
+\t\t\tcategory=\"worker\"
+\t\t}
+\t\tplanet=5
+\t\tsize=12
+\t}
+}
+galactic_object={
+\t0={
+\t\tcoordinate={
+\t\t\tx=10.5
+\t\t\ty=-20
+\t\t}
+\t\ttype=star
+\t\tname=\"Sol\"
+\t\tplanet=5
+\t}
+\t1={
+\t\tcoordinate={
+\t\t\tx=30
+\t\t\ty=40
+\t\t}
+\t\ttype=star
+\t\tname=\"Alpha Centauri\"
+\t}
+}
+planets={
+\tplanet={
+\t\t5={
+\t\t\tname=\"Earth\"
+\t\t\tplanet_class=\"pc_continental\"
+\t\t\torbit=30.000
+\t\t\tplanet_size=16
+\t\t}
+\t}
+}
+";
+
+    #[test]
+    fn score_needs_the_query_in_order() {
+        assert_eq!(score("sol", "Alpha Centauri"), None);
+        assert_eq!(score("lso", "Sol"), None);
+        assert_eq!(score("", "Sol"), Some(0));
+        assert_eq!(score("SOL", "sol"), score("sol", "Sol"));
+    }
+
+    #[test]
+    fn score_prefers_whole_and_connected_matches() {
+        let whole = score("sol", "Sol").unwrap();
+        let piece = score("sol", "Solaris").unwrap();
+        let inside = score("sol", "Ansolar").unwrap();
+        let scattered = score("sol", "Sagittarius Old").unwrap();
+        assert!(whole > piece);
+        assert!(piece > inside);
+        assert!(inside > scattered);
+        // starting words counts for more than being close together
+        assert!(score("ac", "Alpha Centauri").unwrap() > score("ac", "Arcturus").unwrap());
+    }
+
+    #[test]
+    fn find_labels_and_places_results() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        assert!(find(&galaxy, "  ").is_empty());
+        assert!(find(&galaxy, "xyz").is_empty());
+        assert_eq!(find(&galaxy, "sol")[0], (String::from("System: Sol"), 0));
+        assert_eq!(find(&galaxy, "earth"), [(String::from("Planet: Earth"), 0)]);
+        assert_eq!(
+            find(&galaxy, "human"),
+            [(String::from("Species: Human"), 0)]
+        );
+        // what the label says a result is isn't matched
+        assert!(find(&galaxy, "system").is_empty());
+        let found: Vec<usize> = find(&galaxy, "a").iter().map(|x| x.1).collect();
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(&1));
+    }
+}
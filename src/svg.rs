@@ -0,0 +1,313 @@
+use crate::{
+    colors::ColorTable,
+    map::{self, Camera},
+    saveread::{GalObject, Galaxy},
+};
+use std::{collections::HashMap, fmt::Write};
+
+///the number of corners of the circle each system's territory is cut from.
+const CORNERS: usize = 32;
+
+///returns the galaxy as an svg image of the size, with the territory of every empire, the
+/// hyperlanes, the systems, a label for every empire and a legend. Every element carries the
+/// game id of what it shows, as id="system-12" and data-id="12", so it can be styled or scripted.
+pub fn galaxy(galaxy: &Galaxy, table: &ColorTable, width: usize, height: usize) -> String {
+    let camera = Camera::fit(galaxy.maxc(), (0, 0, width, height));
+    let place = |a: &GalObject| camera.project((a.gx(), a.gy()));
+    let colors = map::empire_colors(galaxy, table);
+    let systems: Vec<&GalObject> = galaxy.get_obj_iter().collect();
+    let by_id: HashMap<usize, &GalObject> = systems.iter().map(|x| (x.id(), *x)).collect();
+    let mut ret = String::new();
+    // writing to a String can't fail
+    let _ = writeln!(
+        ret,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height
+    );
+    let _ = writeln!(
+        ret,
+        "<rect id=\"background\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width,
+        height,
+        hex(map::BACKGROUND)
+    );
+
+    ret.push_str("<g id=\"territory\">\n");
+    let radius = map::RADIUS * camera.zoom();
+    for empire in galaxy.get_empire_iter() {
+        let owned: Vec<&GalObject> = systems
+            .iter()
+            .filter(|x| x.owner() == Some(empire.id()))
+            .copied()
+            .collect();
+        let color = match colors.get(&empire.id()) {
+            Some(a) if !owned.is_empty() => hex(*a),
+            _ => continue,
+        };
+        let _ = writeln!(
+            ret,
+            "<g id=\"territory-{0}\" class=\"territory\" data-id=\"{0}\">",
+            empire.id()
+        );
+        let mut border = String::new();
+        for system in owned {
+            let cell = cell(system, &systems, place, radius);
+            let points: Vec<String> = cell
+                .iter()
+                .map(|((x, y), _)| format!("{:.1},{:.1}", x, y))
+                .collect();
+            let _ = writeln!(
+                ret,
+                "<polygon data-system=\"{}\" points=\"{}\" fill=\"{}\" fill-opacity=\"0.5\"/>",
+                system.id(),
+                points.join(" "),
+                color
+            );
+            // edges against empty space or another owner make up the border
+            for i in 0..cell.len() {
+                let ((ax, ay), edge) = cell[i];
+                let ((bx, by), _) = cell[(i + 1) % cell.len()];
+                if edge.is_none_or(|x| by_id[&x].owner() != system.owner()) {
+                    let _ = write!(border, "M{:.1},{:.1}L{:.1},{:.1}", ax, ay, bx, by);
+                }
+            }
+        }
+        let _ = writeln!(
+            ret,
+            "<path class=\"border\" d=\"{}\" fill=\"none\" stroke=\"{}\"/>\n</g>",
+            border, color
+        );
+    }
+    ret.push_str("</g>\n<g id=\"hyperlanes\">\n");
+    for a in systems.iter() {
+        for b in a.get_hyperlane_iter() {
+            let b = match by_id.get(b) {
+                Some(b) if b.id() > a.id() => b,
+                _ => continue,
+            };
+            let stroke = map::lane_stroke(a, b, &colors);
+            let ((x1, y1), (x2, y2)) = (place(a), place(b));
+            let dash = match stroke.dash {
+                Some((on, off)) => format!(" stroke-dasharray=\"{} {}\"", on, off),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                ret,
+                "<line class=\"hyperlane\" data-from=\"{}\" data-to=\"{}\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"{}\"{}/>",
+                a.id(),
+                b.id(),
+                x1,
+                y1,
+                x2,
+                y2,
+                hex(stroke.color),
+                stroke.width,
+                dash
+            );
+        }
+    }
+    ret.push_str("</g>\n<g id=\"systems\">\n");
+    for a in systems.iter() {
+        let (x, y) = place(a);
+        let owner = match a.owner() {
+            Some(b) => format!(" data-owner=\"{}\"", b),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            ret,
+            "<circle id=\"system-{0}\" class=\"system {1}\" data-id=\"{0}\" data-name=\"{2}\"{3} cx=\"{4:.1}\" cy=\"{5:.1}\" r=\"3\" fill=\"#FFFF00\"><title>{2}</title></circle>",
+            a.id(),
+            escape(a.typ()),
+            escape(a.get_name()),
+            owner,
+            x,
+            y
+        );
+    }
+    ret.push_str("</g>\n<g id=\"labels\">\n");
+    let mut legend = Vec::new();
+    for empire in galaxy.get_empire_iter() {
+        let owned: Vec<(f64, f64)> = systems
+            .iter()
+            .filter(|x| x.owner() == Some(empire.id()))
+            .map(|x| place(x))
+            .collect();
+        if owned.is_empty() {
+            continue;
+        }
+        let color = colors.get(&empire.id()).copied().unwrap_or(map::LANE);
+        legend.push((empire.id(), empire.name(), color));
+        let count = owned.len() as f64;
+        let x = owned.iter().map(|a| a.0).sum::<f64>() / count;
+        let y = owned.iter().map(|a| a.1).sum::<f64>() / count;
+        let _ = writeln!(
+            ret,
+            "<text id=\"label-{0}\" class=\"label\" data-id=\"{0}\" x=\"{1:.1}\" y=\"{2:.1}\" font-size=\"{3:.0}\" text-anchor=\"middle\" fill=\"#FFFFFF\" stroke=\"#000000\" stroke-width=\"0.5\">{4}</text>",
+            empire.id(),
+            x,
+            y,
+            10.0 + 2.0 * count.sqrt(),
+            escape(empire.name())
+        );
+    }
+    ret.push_str("</g>\n<g id=\"legend\">\n");
+    let _ = writeln!(
+        ret,
+        "<rect x=\"5\" y=\"5\" width=\"180\" height=\"{}\" fill=\"#F0F0F0\" fill-opacity=\"0.8\"/>",
+        legend.len() * 16 + 8
+    );
+    for (i, (id, name, color)) in legend.iter().enumerate() {
+        let y = 9 + i * 16;
+        let _ = writeln!(
+            ret,
+            "<g id=\"legend-{0}\" data-id=\"{0}\"><rect x=\"9\" y=\"{1}\" width=\"12\" height=\"12\" fill=\"{2}\"/><text x=\"26\" y=\"{3}\" font-size=\"11\">{4}</text></g>",
+            id,
+            y,
+            hex(*color),
+            y + 10,
+            escape(name)
+        );
+    }
+    ret.push_str("</g>\n</svg>\n");
+    ret
+}
+
+///returns the part of the circle around the system that is closer to it than to any other system,
+/// matching what map::territory colors. Every corner comes with what the edge from it to the next
+/// corner borders: the id of the system on the other side, or None for empty space.
+fn cell(
+    system: &GalObject,
+    systems: &[&GalObject],
+    place: impl Fn(&GalObject) -> (f64, f64),
+    radius: f64,
+) -> Vec<((f64, f64), Option<usize>)> {
+    let (cx, cy) = place(system);
+    let mut ret: Vec<((f64, f64), Option<usize>)> = (0..CORNERS)
+        .map(|i| {
+            let angle = i as f64 * std::f64::consts::TAU / CORNERS as f64;
+            ((cx + radius * angle.cos(), cy + radius * angle.sin()), None)
+        })
+        .collect();
+    for other in systems.iter() {
+        let (ox, oy) = place(other);
+        let (dx, dy) = (ox - cx, oy - cy);
+        let dist = dx * dx + dy * dy;
+        if other.id() == system.id() || dist > 4.0 * radius * radius || dist == 0.0 {
+            continue;
+        }
+        // keeps the points p with p·d <= c, the half closer to the system
+        let c = (dx * (cx + ox) + dy * (cy + oy)) / 2.0;
+        let inside = |p: (f64, f64)| p.0 * dx + p.1 * dy <= c;
+        let mut next = Vec::with_capacity(ret.len() + 1);
+        for i in 0..ret.len() {
+            let (p, edge) = ret[i];
+            let (q, _) = ret[(i + 1) % ret.len()];
+            let cross = || {
+                let (a, b) = (p.0 * dx + p.1 * dy - c, q.0 * dx + q.1 * dy - c);
+                let t = a / (a - b);
+                (p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1))
+            };
+            match (inside(p), inside(q)) {
+                (true, true) => next.push((p, edge)),
+                (true, false) => {
+                    next.push((p, edge));
+                    next.push((cross(), Some(other.id())));
+                }
+                (false, true) => next.push((cross(), edge)),
+                (false, false) => {}
+            }
+        }
+        ret = next;
+        if ret.is_empty() {
+            break;
+        }
+    }
+    ret
+}
+
+///formats a 0xRRGGBB color the way svg takes it.
+fn hex(color: u32) -> String {
+    format!("#{:06X}", color & 0xFFFFFF)
+}
+
+///escapes text for use in svg, both between tags and in attributes.
+fn escape(inp: &str) -> String {
+    inp.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///three unowned systems: two 30 apart, whose territories meet, and one far from both.
+    const SAVE: &str = "version=\"Shelley v2.5.1\"
+version_control_revision=1
+name=\"Test\"
+date=\"2210.10.19\"
+galactic_object={
+\t0={
+\t\tcoordinate={
+\t\t\tx=0
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Sol & <Home>\"
+\t}
+\t1={
+\t\tcoordinate={
+\t\t\tx=30
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Vega\"
+\t}
+\t2={
+\t\tcoordinate={
+\t\t\tx=200
+\t\t\ty=0
+\t\t}
+\t\ttype=star
+\t\tname=\"Deneb\"
+\t}
+}
+";
+
+    #[test]
+    fn escape_replaces_markup() {
+        assert_eq!(escape("A & <B> \"C\""), "A &amp; &lt;B&gt; &quot;C&quot;");
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+        assert_eq!(escape("Sol"), "Sol");
+    }
+
+    #[test]
+    fn names_are_escaped_in_the_image() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        let svg = super::galaxy(&galaxy, &ColorTable::new(), 300, 200);
+        assert!(svg.contains("data-name=\"Sol &amp; &lt;Home&gt;\""));
+        assert!(svg.contains("<title>Sol &amp; &lt;Home&gt;</title>"));
+        assert!(!svg.contains("<Home>"));
+    }
+
+    #[test]
+    fn cells_are_cut_where_systems_meet() {
+        let galaxy = Galaxy::gamestate(SAVE);
+        let systems: Vec<&GalObject> = galaxy.get_obj_iter().collect();
+        let place = |a: &GalObject| (a.gx(), a.gy());
+        let sol = cell(systems[0], &systems, place, 25.0);
+        // Sol keeps the half nearer to it, up to halfway to Vega
+        assert!(sol.iter().all(|((x, _), _)| *x <= 15.0 + 1e-9));
+        assert!(sol
+            .iter()
+            .all(|((x, y), _)| (x * x + y * y).sqrt() <= 25.0 + 1e-9));
+        let edges: Vec<Option<usize>> = sol.iter().map(|x| x.1).collect();
+        assert_eq!(edges.iter().filter(|x| **x == Some(1)).count(), 1);
+        assert!(edges.iter().all(|x| x.is_none() || *x == Some(1)));
+        // Deneb is too far to be cut by either
+        let deneb = cell(systems[2], &systems, place, 25.0);
+        assert_eq!(deneb.len(), CORNERS);
+        assert!(deneb.iter().all(|x| x.1.is_none()));
+    }
+}
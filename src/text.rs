@@ -0,0 +1,509 @@
+use crate::{FromImage, OPAQUE};
+use std::collections::HashMap;
+
+///the built-in font: 5 columns of 7 pixels for every character from ' ' to '~',
+/// with the top pixel in the lowest bit.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5F, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14],
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00],
+    [0x14, 0x08, 0x3E, 0x08, 0x14],
+    [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3E, 0x51, 0x49, 0x45, 0x3E],
+    [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4B, 0x31],
+    [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3C, 0x4A, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1E],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E],
+    [0x7F, 0x49, 0x49, 0x49, 0x36],
+    [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C],
+    [0x7F, 0x49, 0x49, 0x49, 0x41],
+    [0x7F, 0x09, 0x09, 0x09, 0x01],
+    [0x3E, 0x41, 0x49, 0x49, 0x7A],
+    [0x7F, 0x08, 0x08, 0x08, 0x7F],
+    [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01],
+    [0x7F, 0x08, 0x14, 0x22, 0x41],
+    [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F],
+    [0x7F, 0x04, 0x08, 0x10, 0x7F],
+    [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06],
+    [0x3E, 0x41, 0x51, 0x21, 0x5E],
+    [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7F, 0x01, 0x01],
+    [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F],
+    [0x3F, 0x40, 0x38, 0x40, 0x3F],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7F, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7E, 0x09, 0x01, 0x02],
+    [0x0C, 0x52, 0x52, 0x52, 0x3E],
+    [0x7F, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7D, 0x40, 0x00],
+    [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x7F, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7F, 0x40, 0x00],
+    [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C],
+    [0x7C, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20],
+    [0x3C, 0x40, 0x40, 0x20, 0x7C],
+    [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+///accented letters made from a letter of GLYPHS and a mark from MARKS.
+const ACCENTED: [(char, char, usize); 58] = [
+    ('À', 'A', 1),
+    ('Á', 'A', 0),
+    ('Â', 'A', 2),
+    ('Ã', 'A', 4),
+    ('Ä', 'A', 3),
+    ('Å', 'A', 5),
+    ('Ç', 'C', 6),
+    ('È', 'E', 1),
+    ('É', 'E', 0),
+    ('Ê', 'E', 2),
+    ('Ë', 'E', 3),
+    ('Ì', 'I', 1),
+    ('Í', 'I', 0),
+    ('Î', 'I', 2),
+    ('Ï', 'I', 3),
+    ('Ñ', 'N', 4),
+    ('Ò', 'O', 1),
+    ('Ó', 'O', 0),
+    ('Ô', 'O', 2),
+    ('Õ', 'O', 4),
+    ('Ö', 'O', 3),
+    ('Ù', 'U', 1),
+    ('Ú', 'U', 0),
+    ('Û', 'U', 2),
+    ('Ü', 'U', 3),
+    ('Ý', 'Y', 0),
+    ('Š', 'S', 7),
+    ('Ž', 'Z', 7),
+    ('Č', 'C', 7),
+    ('à', 'a', 1),
+    ('á', 'a', 0),
+    ('â', 'a', 2),
+    ('ã', 'a', 4),
+    ('ä', 'a', 3),
+    ('å', 'a', 5),
+    ('ç', 'c', 6),
+    ('è', 'e', 1),
+    ('é', 'e', 0),
+    ('ê', 'e', 2),
+    ('ë', 'e', 3),
+    ('ì', 'i', 1),
+    ('í', 'i', 0),
+    ('î', 'i', 2),
+    ('ï', 'i', 3),
+    ('ñ', 'n', 4),
+    ('ò', 'o', 1),
+    ('ó', 'o', 0),
+    ('ô', 'o', 2),
+    ('õ', 'o', 4),
+    ('ö', 'o', 3),
+    ('ù', 'u', 1),
+    ('ú', 'u', 0),
+    ('û', 'u', 2),
+    ('ü', 'u', 3),
+    ('ý', 'y', 0),
+    ('š', 's', 7),
+    ('ž', 'z', 7),
+    ('č', 'c', 7),
+];
+
+///the marks of ACCENTED, as two rows of 5 pixels with the leftmost pixel in the lowest bit:
+/// acute, grave, circumflex, diaeresis, tilde, ring, cedilla and caron. The cedilla goes under
+/// the letter, the rest over it.
+const MARKS: [[u8; 2]; 8] = [
+    [0x08, 0x04],
+    [0x02, 0x04],
+    [0x04, 0x0A],
+    [0x00, 0x0A],
+    [0x16, 0x0D],
+    [0x04, 0x04],
+    [0x04, 0x0C],
+    [0x0A, 0x04],
+];
+
+///letters that aren't a letter with a mark, given like GLYPHS.
+const EXTRA: [(char, [u8; 5]); 6] = [
+    ('Æ', [0x7E, 0x09, 0x7F, 0x49, 0x49]),
+    ('æ', [0x20, 0x54, 0x78, 0x54, 0x58]),
+    ('Ø', [0x5E, 0x31, 0x49, 0x46, 0x3D]),
+    ('ø', [0x58, 0x64, 0x54, 0x4C, 0x34]),
+    ('ß', [0x7E, 0x01, 0x49, 0x4E, 0x30]),
+    ('°', [0x00, 0x06, 0x09, 0x06, 0x00]),
+];
+
+///pairs of characters drawn a pixel closer together than their widths say.
+const KERNING: [(char, char); 18] = [
+    ('A', 'V'),
+    ('A', 'W'),
+    ('A', 'Y'),
+    ('A', 'T'),
+    ('V', 'A'),
+    ('W', 'A'),
+    ('Y', 'A'),
+    ('T', 'A'),
+    ('L', 'T'),
+    ('L', 'Y'),
+    ('L', 'V'),
+    ('P', 'A'),
+    ('F', 'A'),
+    ('T', 'o'),
+    ('T', 'a'),
+    ('T', 'e'),
+    ('Y', 'o'),
+    ('r', '.'),
+];
+
+///rows over the letters of GLYPHS, for marks on capitals.
+const TOP: usize = 2;
+
+///where the lines of a text go in the space given to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+///a proportional bitmap font. Every glyph is a list of columns, with the top pixel in the lowest
+/// bit, and is as wide as its leftmost to its rightmost pixel. Characters the font lacks are
+/// drawn as '?'.
+#[derive(Debug, Clone)]
+pub struct Font {
+    glyphs: HashMap<char, Vec<u32>>,
+    height: usize,
+    kerning: HashMap<(char, char), i64>,
+}
+
+impl Font {
+    ///returns the built-in font: printable ascii, the accented letters common in empire names,
+    /// and a few other letters.
+    pub fn new() -> Font {
+        let mut glyphs = HashMap::new();
+        let column = |x: u8| (x as u32) << TOP;
+        for (i, glyph) in GLYPHS.iter().enumerate() {
+            glyphs.insert(
+                (b' ' + i as u8) as char,
+                glyph.iter().map(|x| column(*x)).collect(),
+            );
+        }
+        for (c, glyph) in EXTRA.iter() {
+            glyphs.insert(*c, glyph.iter().map(|x| column(*x)).collect());
+        }
+        for (c, base, mark) in ACCENTED.iter() {
+            let mut glyph: Vec<u32> = GLYPHS[*base as usize - ' ' as usize]
+                .iter()
+                .map(|x| column(*x))
+                .collect();
+            // lowercase letters have room for the mark in their own top rows, where the dot of i is
+            let over = if base.is_lowercase() {
+                for x in glyph.iter_mut() {
+                    *x &= !(0b11 << TOP);
+                }
+                TOP
+            } else {
+                0
+            };
+            let rows: [(u8, usize); 2] = match mark {
+                6 => [(MARKS[6][0], TOP + 7), (MARKS[6][1], TOP + 8)],
+                _ => [(MARKS[*mark][0], over), (MARKS[*mark][1], over + 1)],
+            };
+            for (x, column) in glyph.iter_mut().enumerate() {
+                for (row, y) in rows.iter() {
+                    if row >> x & 1 == 1 {
+                        *column |= 1 << y;
+                    }
+                }
+            }
+            glyphs.insert(*c, glyph);
+        }
+        for glyph in glyphs.values_mut() {
+            trim(glyph);
+        }
+        Font {
+            glyphs,
+            height: TOP + 9,
+            kerning: KERNING.iter().map(|x| (*x, -1)).collect(),
+        }
+    }
+    ///the height of a line of text, with room between lines, at size 1.
+    pub fn line_height(&self) -> usize {
+        self.height + 1
+    }
+    fn glyph(&self, c: char) -> &[u32] {
+        match self.glyphs.get(&c) {
+            Some(a) => a,
+            None => &self.glyphs[&'?'],
+        }
+    }
+    ///returns where every character of the line starts, and the width of the line, at size 1.
+    fn places(&self, line: &str) -> (Vec<i64>, usize) {
+        let mut ret = Vec::new();
+        let mut x = 0i64;
+        let mut last = None;
+        for c in line.chars() {
+            if let Some(a) = last {
+                x += 1 + self.kerning.get(&(a, c)).copied().unwrap_or(0);
+            }
+            x = x.max(0);
+            ret.push(x);
+            x += self.glyph(c).len() as i64;
+            last = Some(c);
+        }
+        (ret, x.max(0) as usize)
+    }
+    ///the width of a line of text at the size.
+    pub fn measure(&self, line: &str, size: usize) -> usize {
+        self.places(line).1 * size
+    }
+    ///splits the text into lines at its line breaks, and between words where a line would be
+    /// wider than width. A word wider than width gets a line, or lines, of its own.
+    pub fn wrap(&self, inp: &str, size: usize, width: Option<usize>) -> Vec<String> {
+        let width = match width {
+            Some(a) => a,
+            None => return inp.lines().map(String::from).collect(),
+        };
+        let mut ret = Vec::new();
+        for paragraph in inp.lines() {
+            let mut line = String::new();
+            for (i, word) in paragraph.split(' ').enumerate() {
+                let next = match i {
+                    0 => String::from(word),
+                    _ => format!("{} {}", line, word),
+                };
+                if self.measure(&next, size) <= width {
+                    line = next;
+                    continue;
+                }
+                // the word starts a new line, broken up if it's too wide for one itself
+                if i > 0 {
+                    ret.push(line);
+                }
+                line = String::new();
+                for c in word.chars() {
+                    line.push(c);
+                    if self.measure(&line, size) > width && line.chars().count() > 1 {
+                        line.pop();
+                        ret.push(line);
+                        line = c.to_string();
+                    }
+                }
+            }
+            ret.push(line);
+        }
+        ret
+    }
+    ///writes the text in the color on a see-through image, with every pixel of the font as a
+    /// square of size pixels. With a width the text is wrapped to it and aligned within it,
+    /// otherwise the image is as wide as the widest line. Lines below height are left out.
+    pub fn render(
+        &self,
+        inp: &str,
+        color: u32,
+        size: usize,
+        width: Option<usize>,
+        height: Option<usize>,
+        align: Align,
+    ) -> FromImage {
+        let size = size.max(1);
+        let mut lines = self.wrap(inp, size, width);
+        let line = self.line_height() * size;
+        if let Some(a) = height {
+            lines.truncate(a / line);
+        }
+        let width = width.unwrap_or_else(|| {
+            lines
+                .iter()
+                .map(|x| self.measure(x, size))
+                .max()
+                .unwrap_or(0)
+        });
+        let mut ret = FromImage::blank(width.max(1), (lines.len() * line).max(1));
+        for (row, text) in lines.iter().enumerate() {
+            let (places, length) = self.places(text);
+            let start = match align {
+                Align::Left => 0,
+                Align::Center => width.saturating_sub(length * size) / 2,
+                Align::Right => width.saturating_sub(length * size),
+            };
+            for (c, place) in text.chars().zip(places) {
+                for (x, column) in self.glyph(c).iter().enumerate() {
+                    for y in (0..self.height).filter(|y| column >> y & 1 == 1) {
+                        for dy in 0..size {
+                            for dx in 0..size {
+                                let px = start + (place as usize + x) * size + dx;
+                                if px < width {
+                                    ret[row * line + y * size + dy][px] = OPAQUE | color;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+}
+
+impl Default for Font {
+    fn default() -> Font {
+        Font::new()
+    }
+}
+
+///removes the empty columns at both sides of a glyph, leaving spaces and other empty glyphs
+/// two columns wide.
+fn trim(glyph: &mut Vec<u32>) {
+    match glyph.iter().position(|x| *x != 0) {
+        Some(first) => {
+            let last = glyph.iter().rposition(|x| *x != 0).unwrap_or(first);
+            glyph.truncate(last + 1);
+            glyph.drain(..first);
+        }
+        None => glyph.truncate(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///the columns of the image with any pixel drawn.
+    fn columns(image: &FromImage) -> Vec<usize> {
+        (0..image.width())
+            .filter(|x| (0..image.len()).any(|y| image[y][*x] != 0))
+            .collect()
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        let font = Font::new();
+        let width = font.measure("ab cd", 1);
+        assert_eq!(font.wrap("ab cd ef", 1, Some(width)), ["ab cd", "ef"]);
+        assert_eq!(
+            font.wrap("ab cd ef", 1, Some(width - 1)),
+            ["ab", "cd", "ef"]
+        );
+        assert_eq!(font.wrap("ab cd ef", 2, Some(2 * width)), ["ab cd", "ef"]);
+        assert_eq!(font.wrap("ab\ncd ef", 1, None), ["ab", "cd ef"]);
+        assert_eq!(font.wrap("ab\ncd ef", 1, Some(width)), ["ab", "cd ef"]);
+    }
+
+    #[test]
+    fn wrap_breaks_up_words_wider_than_the_line() {
+        let font = Font::new();
+        let width = font.measure("abc", 1);
+        assert_eq!(font.wrap("abcdef g", 1, Some(width)), ["abc", "def", "g"]);
+        // a line always gets a character, however narrow
+        assert_eq!(font.wrap("ab", 1, Some(0)), ["a", "b"]);
+    }
+
+    #[test]
+    fn render_aligns_the_lines_in_the_width() {
+        let font = Font::new();
+        let length = font.measure("ab", 2);
+        let drawn = |align| columns(&font.render("ab", 0xFFFFFF, 2, Some(40), None, align));
+        assert_eq!(drawn(Align::Left)[0], 0);
+        assert_eq!(drawn(Align::Center)[0], (40 - length) / 2);
+        assert_eq!(*drawn(Align::Right).last().unwrap(), 39);
+        let image = font.render("ab", 0x123456, 2, None, None, Align::Left);
+        assert_eq!(
+            (image.width(), image.len()),
+            (length, 2 * font.line_height())
+        );
+        assert!(image
+            .content
+            .iter()
+            .all(|x| *x == 0 || *x == OPAQUE | 0x123456));
+    }
+
+    #[test]
+    fn render_leaves_out_lines_below_the_height() {
+        let font = Font::new();
+        let line = font.line_height();
+        let image = font.render("a\nb\nc", 0, 1, Some(20), Some(2 * line + 1), Align::Left);
+        assert_eq!(image.len(), 2 * line);
+    }
+
+    #[test]
+    fn accented_letters_are_their_letter_with_a_mark() {
+        let font = Font::new();
+        for (accented, base) in [('É', 'E'), ('é', 'e'), ('Ç', 'C'), ('ç', 'c')] {
+            let (accented, base) = (font.glyph(accented), font.glyph(base));
+            assert_eq!(accented.len(), base.len());
+            assert_ne!(accented, base);
+            // the marks only take rows the letter leaves empty: over or under it, or the top two
+            // of a lowercase letter
+            for (a, b) in accented.iter().zip(base.iter()) {
+                assert_eq!(a & b, *b);
+                assert_eq!((a & !b) & 0x7C << TOP, 0);
+            }
+        }
+        assert_eq!(font.glyph('☃'), font.glyph('?'));
+    }
+
+    #[test]
+    fn kerned_pairs_are_drawn_closer() {
+        let font = Font::new();
+        let apart = |a: &str, b: &str| font.measure(a, 1) + 1 + font.measure(b, 1);
+        assert_eq!(font.measure("AV", 1), apart("A", "V") - 1);
+        assert_eq!(font.measure("VA", 1), apart("V", "A") - 1);
+        assert_eq!(font.measure("AB", 1), apart("A", "B"));
+        assert_eq!(font.measure("AV", 3), 3 * font.measure("AV", 1));
+    }
+}
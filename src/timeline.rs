@@ -0,0 +1,220 @@
+use crate::{FromImage, Stroke};
+
+///a slider along the dates of the saves, drawn in area (x, y, width, height) on screen.
+/// Dates are placed by how far apart they are, so gaps between saves show as gaps on the slider.
+/// While playing, time runs on between the dates, and current is the last save before it.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    dates: Vec<usize>,
+    current: usize,
+    time: f64,
+    playing: bool,
+    area: (usize, usize, usize, usize),
+}
+
+impl Timeline {
+    ///returns a timeline over dates, which have to be sorted, set to the last of them,
+    /// or an error if there are none.
+    pub fn new(dates: Vec<usize>, area: (usize, usize, usize, usize)) -> Result<Timeline, String> {
+        let current = dates
+            .len()
+            .checked_sub(1)
+            .ok_or("No saves to make a timeline of")?;
+        Ok(Timeline {
+            time: dates[current] as f64,
+            current,
+            dates,
+            playing: false,
+            area,
+        })
+    }
+    ///returns the selected date.
+    pub fn date(&self) -> usize {
+        self.dates[self.current]
+    }
+    ///returns the date of the next save and how far time has come towards it, from 0 to 1,
+    /// or None when time is exactly on a save.
+    pub fn blend(&self) -> Option<(usize, f64)> {
+        let next = *self.dates.get(self.current + 1)?;
+        let date = self.date() as f64;
+        if self.time > date {
+            Some((next, (self.time - date) / (next as f64 - date)))
+        } else {
+            None
+        }
+    }
+    ///returns the first and last date.
+    pub fn range(&self) -> (usize, usize) {
+        (self.dates[0], self.dates[self.dates.len() - 1])
+    }
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+    ///returns all dates, and the index of the selected one among them.
+    pub fn dates(&self) -> (&[usize], usize) {
+        (&self.dates, self.current)
+    }
+    ///selects the date with index i. Returns whether the date changed.
+    pub fn select(&mut self, i: usize) -> bool {
+        i < self.dates.len() && self.jump(i)
+    }
+    pub fn area(&self) -> (usize, usize, usize, usize) {
+        self.area
+    }
+    pub fn resize(&mut self, area: (usize, usize, usize, usize)) {
+        self.area = area;
+    }
+    ///starts or stops playing. Playing from the last save starts over from the first.
+    pub fn play(&mut self) {
+        self.playing = !self.playing;
+        if self.playing && self.current + 1 == self.dates.len() {
+            self.jump(0);
+        }
+    }
+    ///lets days pass while playing, stopping at the last save. Returns whether anything changed.
+    pub fn advance(&mut self, days: f64) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let last = self.dates.len() - 1;
+        self.time = (self.time + days).min(self.dates[last] as f64);
+        while self.current < last && self.dates[self.current + 1] as f64 <= self.time {
+            self.current += 1;
+        }
+        if self.current == last {
+            self.playing = false;
+        }
+        true
+    }
+    ///whether the screen coordinates are on the slider.
+    pub fn contains(&self, inp: (f64, f64)) -> bool {
+        let (x, y, width, height) = self.area;
+        inp.0 >= x as f64
+            && inp.0 < (x + width) as f64
+            && inp.1 >= y as f64
+            && inp.1 < (y + height) as f64
+    }
+    ///moves by steps dates, stopping at the first and last. Returns whether the date changed.
+    pub fn step(&mut self, steps: i64) -> bool {
+        let next = (self.current as i64 + steps).clamp(0, self.dates.len() as i64 - 1) as usize;
+        self.jump(next)
+    }
+    ///selects the date closest to the x coordinate on screen. Returns whether the date changed.
+    pub fn seek(&mut self, x: f64) -> bool {
+        let x = x - self.area.0 as f64;
+        let next = (0..self.dates.len())
+            .min_by(|a, b| {
+                (self.offset(self.dates[*a] as f64) - x)
+                    .abs()
+                    .total_cmp(&(self.offset(self.dates[*b] as f64) - x).abs())
+            })
+            .unwrap_or(0);
+        self.jump(next)
+    }
+    ///returns the slider as an image to attach at the top left corner of its area:
+    /// a track with a tick for every date, and a knob on the selected one.
+    pub fn draw(&self) -> FromImage {
+        let (_, _, width, height) = self.area;
+        let mut ret = FromImage::blank(width, height);
+        let middle = height as f64 / 2.0;
+        let track = |color, width| Stroke {
+            color,
+            width,
+            dash: None,
+        };
+        ret.line(
+            (KNOB, middle),
+            (width as f64 - KNOB, middle),
+            track(0x333333, 2.0),
+        );
+        for i in self.dates.iter() {
+            let x = self.offset(*i as f64);
+            ret.line((x, middle - 4.0), (x, middle + 4.0), track(0x333333, 1.0));
+        }
+        let x = self.offset(self.time);
+        ret.line((x, 1.0), (x, height as f64 - 2.0), track(0x2E6BD9, KNOB));
+        ret
+    }
+    ///selects the date with index i, moving time there. Returns whether the date changed.
+    fn jump(&mut self, i: usize) -> bool {
+        let ret = i != self.current || self.time != self.dates[i] as f64;
+        self.current = i;
+        self.time = self.dates[i] as f64;
+        ret
+    }
+    ///returns how far from the left of the area the date is drawn.
+    fn offset(&self, date: f64) -> f64 {
+        let length = self.area.2 as f64 - 2.0 * KNOB;
+        let (first, last) = (self.dates[0], self.dates[self.dates.len() - 1]);
+        if last > first {
+            KNOB + length * (date - first as f64) / (last - first) as f64
+        } else {
+            KNOB + length / 2.0
+        }
+    }
+}
+
+///the width of the knob, the track leaves room for half of it at both ends.
+const KNOB: f64 = 6.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///a timeline over three saves, the second closer to the first than to the last.
+    fn timeline() -> Timeline {
+        Timeline::new(vec![100, 130, 400], (0, 0, 112, 20)).unwrap()
+    }
+
+    #[test]
+    fn new_needs_dates() {
+        assert!(Timeline::new(Vec::new(), (0, 0, 100, 20)).is_err());
+        assert_eq!(timeline().date(), 400);
+    }
+
+    #[test]
+    fn step_stops_at_the_ends() {
+        let mut a = timeline();
+        assert!(!a.step(1));
+        assert!(a.step(-1));
+        assert_eq!(a.date(), 130);
+        assert!(a.step(-5));
+        assert_eq!(a.date(), 100);
+        assert!(!a.step(-1));
+        assert!(a.step(2));
+        assert_eq!(a.date(), 400);
+    }
+
+    #[test]
+    fn advance_plays_through_the_dates() {
+        let mut a = timeline();
+        assert!(!a.advance(10.0));
+        // playing from the last save starts over
+        a.play();
+        assert!(a.playing());
+        assert_eq!(a.date(), 100);
+        assert!(a.advance(15.0));
+        assert_eq!(a.date(), 100);
+        assert_eq!(a.blend(), Some((130, 0.5)));
+        assert!(a.advance(30.0));
+        assert_eq!(a.date(), 130);
+        assert!(a.advance(1000.0));
+        assert_eq!(a.date(), 400);
+        assert_eq!(a.blend(), None);
+        assert!(!a.playing());
+    }
+
+    #[test]
+    fn seek_picks_the_closest_date() {
+        // the track runs from 6 to 106, so a day is a third of a pixel
+        let mut a = timeline();
+        assert!(a.seek(14.0));
+        assert_eq!(a.date(), 130);
+        assert!(a.seek(0.0));
+        assert_eq!(a.date(), 100);
+        assert!(!a.seek(3.0));
+        assert!(a.select(2));
+        assert!(!a.select(3));
+        assert_eq!(a.date(), 400);
+    }
+}
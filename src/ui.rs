@@ -0,0 +1,451 @@
+use crate::{text::Align, FromImage, Img, Stroke, OPAQUE};
+use minifb::{InputCallback, Key};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+///an area (x, y, width, height) on screen. Widgets are told apart by their areas,
+/// so no two widgets should share one.
+type Area = (usize, usize, usize, usize);
+
+const FACE: u32 = 0xFFFFFF;
+const HOVER: u32 = 0xE4E4E4;
+const PRESSED: u32 = 0xC8C8C8;
+const BORDER: u32 = 0x333333;
+const TEXT: u32 = 0x000000;
+const HINT: u32 = 0x888888;
+///the color of whatever is chosen or has the keyboard, the same as the knob of the slider.
+const ACCENT: u32 = 0x2E6BD9;
+
+///collects the characters typed into the window for text inputs. Give the window a clone with
+/// Window::set_input_callback, and take what was typed every frame.
+#[derive(Debug, Clone, Default)]
+pub struct Typed(Rc<RefCell<String>>);
+
+impl Typed {
+    ///returns what was typed since the last call.
+    pub fn take(&self) -> String {
+        std::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+impl InputCallback for Typed {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(a) = std::char::from_u32(uni_char) {
+            self.0.borrow_mut().push(a);
+        }
+    }
+}
+
+///the state of the mouse and keyboard in a frame: where the mouse is, whether its left button
+/// is held, how far the wheel turned, the keys pressed and the text typed since the last frame.
+/// keys come again and again while held, pressed only when they go down.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Input {
+    pub mouse: (usize, usize),
+    pub down: bool,
+    pub scroll: f32,
+    pub keys: Vec<Key>,
+    pub pressed: Vec<Key>,
+    pub text: String,
+}
+
+impl Input {
+    ///whether the wheel turned, or keys were pressed or typed, which happen again when the
+    /// same input comes twice.
+    pub fn events(&self) -> bool {
+        self.scroll != 0.0 || !self.keys.is_empty() || !self.text.is_empty()
+    }
+}
+
+///immediate mode widgets. Every frame the viewer calls begin with the input, then the function of
+/// every widget it shows, which draws it and returns what was done with it. Widgets are drawn
+/// on one layer and the lists of open dropdowns on the one above it.
+#[derive(Debug)]
+pub struct Ui {
+    input: Input,
+    was_down: bool,
+    layer: usize,
+    // the widget the mouse went down on, the text input taking keys, and the open dropdown
+    pressed: Option<Area>,
+    focus: Option<Area>,
+    open: Option<Area>,
+    // the list of the open dropdown, as drawn this frame and as drawn the frame before,
+    // which keeps the mouse from the widgets it covers
+    popup: Option<Area>,
+    covered: Option<Area>,
+    scroll: HashMap<Area, usize>,
+}
+
+impl Ui {
+    pub fn new(layer: usize) -> Ui {
+        Ui {
+            input: Input::default(),
+            was_down: false,
+            layer,
+            pressed: None,
+            focus: None,
+            open: None,
+            popup: None,
+            covered: None,
+            scroll: HashMap::new(),
+        }
+    }
+    ///starts a frame, taking away the widgets of the last one.
+    pub fn begin(&mut self, img: &mut Img, input: Input) {
+        self.was_down = self.input.down;
+        self.input = input;
+        self.covered = self.popup.take();
+        img.clear(self.layer);
+        img.clear(self.layer + 1);
+        // clicking anywhere else lets go of the keyboard
+        if self.went_down() && self.focus.is_some_and(|a| !inside(a, self.input.mouse)) {
+            self.focus = None;
+        }
+    }
+    ///ends a frame.
+    pub fn end(&mut self) {
+        if !self.input.down {
+            self.pressed = None;
+        }
+    }
+    ///whether a text input takes the keys, so they shouldn't do anything else.
+    pub fn typing(&self) -> bool {
+        self.focus.is_some()
+    }
+    ///gives the keys to the text input at the area, as if it had been clicked.
+    pub fn focus(&mut self, area: Area) {
+        self.focus = Some(area);
+    }
+    fn went_down(&self) -> bool {
+        self.input.down && !self.was_down
+    }
+    fn went_up(&self) -> bool {
+        !self.input.down && self.was_down
+    }
+    ///whether the mouse is over the area, and not over an open list covering it.
+    fn hover(&self, area: Area) -> bool {
+        let covered = self.covered.is_some_and(|a| inside(a, self.input.mouse));
+        inside(area, self.input.mouse) && !covered
+    }
+    ///whether the area was clicked, with the mouse let go over it after going down on it.
+    fn clicked(&mut self, area: Area) -> bool {
+        let hover = self.hover(area);
+        self.press(area, hover)
+    }
+    ///whether the area was clicked while hover tells whether the mouse is over it, for the
+    /// lists of dropdowns, which nothing covers.
+    fn press(&mut self, area: Area, hover: bool) -> bool {
+        if self.went_down() && hover {
+            self.pressed = Some(area);
+        }
+        self.went_up() && self.pressed == Some(area) && hover
+    }
+    ///the color of a clickable area: darker while held, lighter under the mouse.
+    fn face(&self, area: Area) -> u32 {
+        match (self.hover(area), self.pressed == Some(area)) {
+            (true, true) => PRESSED,
+            (true, false) => HOVER,
+            _ => FACE,
+        }
+    }
+
+    ///a button with the label. Returns whether it was clicked.
+    pub fn button(&mut self, img: &mut Img, label: &str, area: Area) -> bool {
+        let ret = self.clicked(area);
+        frame(img, area, self.face(area), BORDER, self.layer);
+        write(img, label, area, TEXT, Align::Center, self.layer);
+        ret
+    }
+    ///a box with a check in it while value is true, and the label next to it. Clicking either
+    /// switches value. Returns whether it did.
+    pub fn checkbox(&mut self, img: &mut Img, label: &str, value: &mut bool, area: Area) -> bool {
+        let ret = self.clicked(area);
+        if ret {
+            *value = !*value;
+        }
+        let (x, y, width, size) = area;
+        frame(img, (x, y, size, size), self.face(area), BORDER, self.layer);
+        if *value {
+            let (x, y, size) = (x as f64, y as f64, size as f64);
+            let stroke = Stroke {
+                color: ACCENT,
+                width: 2.0,
+                dash: None,
+            };
+            let middle = (x + size * 0.4, y + size * 0.75);
+            img.line(
+                (x + size * 0.2, y + size * 0.5),
+                middle,
+                stroke,
+                Some(self.layer),
+            );
+            img.line(
+                middle,
+                (x + size * 0.8, y + size * 0.25),
+                stroke,
+                Some(self.layer),
+            );
+        }
+        let rest = (x + size + 4, y, width.saturating_sub(size + 4), size);
+        write(img, label, rest, TEXT, Align::Left, self.layer);
+        ret
+    }
+    ///the items one under the other, with a scroll bar and scrolled by the mouse wheel when they
+    /// don't all fit. Clicking an item selects it. Returns whether the selection changed.
+    pub fn list(
+        &mut self,
+        img: &mut Img,
+        items: &[String],
+        selected: &mut Option<usize>,
+        area: Area,
+    ) -> bool {
+        let (x, y, width, height) = area;
+        let row = row(img);
+        let rows = (height.saturating_sub(2) / row).max(1);
+        let most = items.len().saturating_sub(rows);
+        let mut offset = self.scroll.get(&area).copied().unwrap_or(0).min(most);
+        if self.hover(area) && self.input.scroll > 0.0 {
+            offset = offset.saturating_sub(1);
+        } else if self.hover(area) && self.input.scroll < 0.0 {
+            offset = (offset + 1).min(most);
+        }
+        self.scroll.insert(area, offset);
+        frame(img, area, FACE, BORDER, self.layer);
+        let inner = match most {
+            0 => width.saturating_sub(2),
+            _ => width.saturating_sub(8),
+        };
+        let mut ret = false;
+        for (i, item) in items.iter().enumerate().skip(offset).take(rows) {
+            let area = (x + 1, y + 1 + (i - offset) * row, inner, row);
+            if self.clicked(area) && *selected != Some(i) {
+                *selected = Some(i);
+                ret = true;
+            }
+            let (fill, color) = match (*selected == Some(i), self.hover(area)) {
+                (true, _) => (ACCENT, 0xFFFFFF),
+                (false, true) => (HOVER, TEXT),
+                (false, false) => (FACE, TEXT),
+            };
+            frame(img, area, fill, fill, self.layer);
+            write(img, item, area, color, Align::Left, self.layer);
+        }
+        // the scroll bar, only there when some items don't fit
+        let track = height.saturating_sub(2);
+        let thumb = (track * rows / items.len().max(1)).clamp(4.min(track), track);
+        if let Some(top) = ((track - thumb) * offset).checked_div(most) {
+            let top = y + 1 + top;
+            frame(
+                img,
+                (x + width - 7, y + 1, 6, track),
+                HOVER,
+                HOVER,
+                self.layer,
+            );
+            frame(
+                img,
+                (x + width - 6, top, 4, thumb),
+                0x999999,
+                0x999999,
+                self.layer,
+            );
+        }
+        ret
+    }
+    ///shows the selected item, and when clicked opens a list of all the items under it to
+    /// choose from, scrolled by the mouse wheel where they don't fit above the bottom of img.
+    /// A click anywhere else closes it. Returns whether another item was chosen.
+    pub fn dropdown(
+        &mut self,
+        img: &mut Img,
+        items: &[String],
+        selected: &mut usize,
+        area: Area,
+    ) -> bool {
+        let (x, y, width, height) = area;
+        let row = row(img);
+        let room = img.height().saturating_sub(y + height + 2) / row;
+        let shown = items.len().min(room.max(1));
+        let most = items.len().saturating_sub(shown);
+        let mut offset = self.scroll.get(&area).copied().unwrap_or(0).min(most);
+        if self.clicked(area) {
+            self.open = if self.open == Some(area) {
+                None
+            } else {
+                Some(area)
+            };
+            // the list opens with the selected item in it
+            let chosen = (*selected).min(items.len().saturating_sub(1));
+            offset = offset.clamp((chosen + 1).saturating_sub(shown), chosen.min(most));
+        }
+        let popup = (x, y + height, width, shown * row + 2);
+        let scrolled = self.open == Some(area) && inside(popup, self.input.mouse);
+        if scrolled && self.input.scroll > 0.0 {
+            offset = offset.saturating_sub(1);
+        } else if scrolled && self.input.scroll < 0.0 {
+            offset = (offset + 1).min(most);
+        }
+        self.scroll.insert(area, offset);
+        let rows: Vec<(usize, Area)> = (offset..offset + shown)
+            .map(|i| {
+                let top = y + height + 1 + (i - offset) * row;
+                (i, (x + 1, top, width.saturating_sub(2), row))
+            })
+            .collect();
+        let mut ret = false;
+        if self.open == Some(area) {
+            for (i, area) in rows.iter().copied() {
+                if self.press(area, inside(area, self.input.mouse)) {
+                    ret = *selected != i;
+                    *selected = i;
+                    self.open = None;
+                }
+            }
+            if self.went_down()
+                && !inside(area, self.input.mouse)
+                && !inside(popup, self.input.mouse)
+            {
+                self.open = None;
+            }
+        }
+        frame(img, area, self.face(area), BORDER, self.layer);
+        let label = items.get(*selected).map_or("", |x| x.as_str());
+        let rest = (x, y, width.saturating_sub(height), height);
+        write(img, label, rest, TEXT, Align::Left, self.layer);
+        // an arrow pointing down at the right end
+        let (ax, ay) = (
+            (x + width) as f64 - height as f64 / 2.0,
+            y as f64 + height as f64 / 2.0,
+        );
+        for i in 0..4 {
+            let (ax, ay) = (ax, ay - 1.0 + i as f64);
+            let half = 3.0 - i as f64;
+            let stroke = Stroke {
+                color: TEXT,
+                width: 1.0,
+                dash: None,
+            };
+            img.line((ax - half, ay), (ax + half, ay), stroke, Some(self.layer));
+        }
+        if self.open == Some(area) {
+            self.popup = Some(popup);
+            frame(img, popup, FACE, BORDER, self.layer + 1);
+            for (i, area) in rows {
+                let item = &items[i];
+                let hover = inside(area, self.input.mouse);
+                let (fill, color) = match (i == *selected, hover) {
+                    (_, true) => (ACCENT, 0xFFFFFF),
+                    (true, false) => (HOVER, TEXT),
+                    (false, false) => (FACE, TEXT),
+                };
+                frame(img, area, fill, fill, self.layer + 1);
+                write(img, item, area, color, Align::Left, self.layer + 1);
+            }
+        }
+        ret
+    }
+    ///a box to type a line of text into after clicking it, showing the hint while empty.
+    /// Backspace takes away the last character and escape lets go of the keyboard.
+    /// Returns whether enter was pressed in it.
+    pub fn text_input(
+        &mut self,
+        img: &mut Img,
+        value: &mut String,
+        hint: &str,
+        area: Area,
+    ) -> bool {
+        if self.clicked(area) {
+            self.focus = Some(area);
+        }
+        let focused = self.focus == Some(area);
+        let mut ret = false;
+        if focused {
+            value.extend(self.input.text.chars().filter(|x| !x.is_control()));
+            for key in self.input.keys.iter() {
+                if *key == Key::Backspace {
+                    value.pop();
+                }
+            }
+            for key in self.input.pressed.iter() {
+                match key {
+                    Key::Enter | Key::NumPadEnter => ret = true,
+                    Key::Escape => self.focus = None,
+                    _ => {}
+                }
+            }
+        }
+        let focused = self.focus == Some(area);
+        let (x, y, width, height) = area;
+        frame(
+            img,
+            area,
+            FACE,
+            if focused { ACCENT } else { BORDER },
+            self.layer,
+        );
+        if value.is_empty() && !focused {
+            write(img, hint, area, HINT, Align::Left, self.layer);
+            return ret;
+        }
+        // the end of a text too long for the box is shown
+        let room = width.saturating_sub(10);
+        let mut shown = value.as_str();
+        while img.text_size(shown, 1).0 > room {
+            let mut chars = shown.chars();
+            chars.next();
+            shown = chars.as_str();
+        }
+        write(img, shown, area, TEXT, Align::Left, self.layer);
+        if focused {
+            let caret = (x + 4 + img.text_size(shown, 1).0 + 1) as f64;
+            let stroke = Stroke {
+                color: TEXT,
+                width: 1.0,
+                dash: None,
+            };
+            let (top, bottom) = (y as f64 + 3.0, (y + height) as f64 - 4.0);
+            img.line((caret, top), (caret, bottom), stroke, Some(self.layer));
+        }
+        ret
+    }
+}
+
+///whether the point is in the area.
+fn inside(area: Area, (x, y): (usize, usize)) -> bool {
+    x >= area.0 && x < area.0 + area.2 && y >= area.1 && y < area.1 + area.3
+}
+
+///the height of one line of a list.
+fn row(img: &Img) -> usize {
+    img.text_size("A", 1).1 + 4
+}
+
+///draws the area filled with fill, with a one pixel border.
+fn frame(img: &mut Img, area: Area, fill: u32, border: u32, layer: usize) {
+    let (x, y, width, height) = area;
+    if width == 0 || height == 0 {
+        return;
+    }
+    let mut ret = FromImage::from_vec(vec![OPAQUE | fill; width * height], width, false);
+    for i in 0..width {
+        ret[0][i] = OPAQUE | border;
+        ret[height - 1][i] = OPAQUE | border;
+    }
+    for i in 0..height {
+        ret[i][0] = OPAQUE | border;
+        ret[i][width - 1] = OPAQUE | border;
+    }
+    img.attach(ret, (x, y), Some(layer));
+}
+
+///writes the line of text in the middle of the height of area, 4 pixels in from its sides.
+fn write(img: &mut Img, inp: &str, area: Area, color: u32, align: Align, layer: usize) {
+    let (x, y, width, height) = area;
+    let line = img.text_size("A", 1).1;
+    let area = (
+        x + 4,
+        y + height.saturating_sub(line) / 2,
+        width.saturating_sub(8),
+        line,
+    );
+    img.draw_text_box(inp, area, color, 1, align, Some(layer));
+}